@@ -0,0 +1,262 @@
+/// Strategy used to place the query characters inside a candidate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Algorithm {
+    /// Consumes the first occurrence of each query character, or the first
+    /// contiguous occurrence of the whole query when its tier bonus makes it
+    /// score higher.
+    ///
+    /// Runs in linear time, which makes it the right choice for huge lists.
+    #[default]
+    Greedy,
+    /// Searches every subsequence placement for the highest-scoring alignment
+    /// (Smith-Waterman style, like fzf's v2 algorithm).
+    ///
+    /// Costs O(n×m) time and memory, where n = query length, m = candidate length.
+    Optimal,
+}
+
+/// How letter case is treated when comparing query and candidate characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CaseMode {
+    /// Characters must match exactly.
+    #[default]
+    Sensitive,
+    /// Characters are compared after Unicode simple case folding.
+    Insensitive,
+    /// Insensitive, unless the query contains an uppercase character.
+    Smart,
+}
+
+impl CaseMode {
+    /// Returns whether characters should be case folded when matching `query`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use matchr::CaseMode;
+    ///
+    /// assert!(CaseMode::Smart.folds("cargo"));
+    /// assert!(!CaseMode::Smart.folds("Cargo"));
+    /// ```
+    pub fn folds(self, query: &str) -> bool {
+        match self {
+            CaseMode::Sensitive => false,
+            CaseMode::Insensitive => true,
+            CaseMode::Smart => !query.chars().any(char::is_uppercase),
+        }
+    }
+}
+
+/// Maps `c` to its Unicode simple case folding.
+///
+/// `char::to_lowercase` covers most of the table; the remaining entries are
+/// lowercase variants that fold onto another lowercase letter. Characters that
+/// only have a multi-char folding (e.g. `İ`) are left unchanged so that every
+/// candidate char still maps to exactly one position.
+pub(crate) fn fold_char(c: char) -> char {
+    if c.is_ascii() {
+        return c.to_ascii_lowercase();
+    }
+    match c {
+        'ς' => 'σ',
+        'ſ' => 's',
+        'ϐ' => 'β',
+        'ϑ' => 'θ',
+        'ϕ' => 'φ',
+        'ϖ' => 'π',
+        'ϰ' => 'κ',
+        'ϱ' => 'ρ',
+        'ϵ' => 'ε',
+        'ẛ' => 'ṡ',
+        '\u{1FBE}' => 'ι',
+        _ => {
+            let mut lower = c.to_lowercase();
+            match (lower.next(), lower.next()) {
+                (Some(l), None) => l,
+                _ => c,
+            }
+        }
+    }
+}
+
+pub(crate) fn fold_if(c: char, fold: bool) -> char {
+    if fold {
+        fold_char(c)
+    } else {
+        c
+    }
+}
+
+/// How the weight of a match falls off the further it sits into the candidate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PositionDecay {
+    /// The weight drops by the given number of points per character and reaches
+    /// zero after `position_weight / step` characters.
+    Linear(usize),
+    /// The weight is halved after the given number of characters, a third left
+    /// after twice as many, and so on: `position_weight * half / (half + pos)`.
+    ///
+    /// Every matched character keeps at least one point, so matches deep inside
+    /// long candidates such as file paths still discriminate.
+    Hyperbolic(usize),
+}
+
+/// How items with equal scores are ordered in ranked results.
+///
+/// The policy applies to items whose unnormalized [`Match::raw`](crate::Match::raw)
+/// scores tie too. Whatever the policy, items that still tie keep their input order.
+///
+/// # Examples
+///
+/// ```
+/// use matchr::{ScoringConfig, TieBreak};
+///
+/// let config = ScoringConfig {
+///     tie_break: TieBreak::Chain(&[TieBreak::Length, TieBreak::Lexical]),
+///     ..Default::default()
+/// };
+/// let results = matchr::match_items_with("ab", &["abd", "abcd", "abc"], &config);
+/// let names: Vec<&str> = results.iter().map(|(item, _)| *item).collect();
+/// assert_eq!(names, ["abc", "abd", "abcd"]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TieBreak {
+    /// Input order.
+    #[default]
+    Index,
+    /// Shorter candidates first, counted in chars.
+    Length,
+    /// Candidates whose first matched char comes earlier first.
+    Begin,
+    /// Candidates in lexical (byte-wise) order.
+    Lexical,
+    /// Applies each policy in turn until one of them orders the items.
+    Chain(&'static [TieBreak]),
+}
+
+impl TieBreak {
+    /// Returns whether `key` is this policy or part of its chain.
+    pub(crate) fn uses(self, key: TieBreak) -> bool {
+        match self {
+            TieBreak::Chain(policies) => policies.iter().any(|policy| policy.uses(key)),
+            policy => policy == key,
+        }
+    }
+}
+
+/// Options controlling how a query is scored against candidates.
+///
+/// Every matched character adds its position weight and word-boundary bonus to a
+/// raw score, which is then mapped onto `0..=max_score`. The defaults reproduce
+/// the scores of [`score`](crate::score).
+///
+/// # Examples
+///
+/// ```
+/// use matchr::{PositionDecay, ScoringConfig};
+///
+/// let palette = ScoringConfig {
+///     position_decay: PositionDecay::Linear(0),
+///     gap_penalty: 20,
+///     ..Default::default()
+/// };
+/// assert!(matchr::score_with("gc", "gc-tool", &palette) > matchr::score_with("gc", "gxc-tool", &palette));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScoringConfig {
+    /// How query characters are placed in the candidate.
+    pub algorithm: Algorithm,
+    /// How letter case is compared.
+    pub case: CaseMode,
+    /// Weight of a character matched at the very start of the candidate. Default: `100`.
+    pub position_weight: usize,
+    /// How the position weight falls off further into the candidate. Default: `Hyperbolic(8)`.
+    pub position_decay: PositionDecay,
+    /// Percentage of the running score added when a match directly follows the previous one. Default: `10`.
+    pub consecutive_bonus: usize,
+    /// Points removed for every candidate character skipped between two matches. Default: `0`.
+    pub gap_penalty: usize,
+    /// Points per query character added when the candidate equals the query. Default: `200`,
+    /// which makes exact matches always reach `max_score`.
+    ///
+    /// This and the four bonuses below are tiers: a match only earns the bonus of
+    /// the best [`Tier`](crate::Tier) it reaches.
+    pub exact_bonus: usize,
+    /// Points per query character added when the candidate equals the query
+    /// after case folding. Default: `150`.
+    pub folded_exact_bonus: usize,
+    /// Points per query character added when the candidate starts with the query. Default: `80`.
+    pub prefix_bonus: usize,
+    /// Points per query character added when the query appears contiguously at
+    /// the start of a word. Default: `70`.
+    pub word_prefix_bonus: usize,
+    /// Points per query character added when the query appears contiguously
+    /// anywhere in the candidate. Default: `60`.
+    pub substring_bonus: usize,
+    /// Raw points per query character that map to `max_score`. Default: `200`.
+    pub char_score: usize,
+    /// Upper bound of the normalized score. Default: `100`.
+    pub max_score: usize,
+    /// Points added to a match on the first character of the candidate. Default: `50`.
+    pub start_bonus: usize,
+    /// Points added to a match right after a separator (`space`, `-`, `_`, `/`, `.`). Default: `50`.
+    pub boundary_bonus: usize,
+    /// Points added to an uppercase match right after a lowercase character. Default: `40`.
+    pub camel_bonus: usize,
+    /// Matches whitespace-separated query terms independently, in any order, so
+    /// `"push git"` finds `"git push"`. The score is the average term score.
+    /// Default: `false`.
+    pub tokenize: bool,
+    /// In `tokenize` mode, points of the final score removed for every candidate
+    /// character covered by the match spans of two terms. Default: `5`.
+    pub overlap_penalty: usize,
+    /// Number of typos a match may contain: query characters substituted in or
+    /// missing from the candidate, or two adjacent query characters swapped, so
+    /// `"gti"` finds `"git"`. Typos are limited to fewer than half of the query
+    /// characters. When set, matches with typos score below `max_score / 4`, and
+    /// exact subsequence matches are mapped onto the range above it. Default: `0`.
+    pub max_typos: usize,
+    /// How items with equal scores and equal unnormalized scores are ordered by
+    /// `match_items` and the other ranking functions. Default: `TieBreak::Index`, input order.
+    pub tie_break: TieBreak,
+    /// Treats candidates as file paths split into segments by `/` or `\`: matches
+    /// at the start of a segment earn `segment_bonus`, matches in the last segment
+    /// (the basename) earn `basename_bonus`, and a query containing separators
+    /// matches segment by segment, so `"src/cfg"` needs `src` and `cfg` inside
+    /// two path segments, in that order. Default: `false`.
+    pub path: bool,
+    /// In `path` mode, points added to a match at the start of a path segment. Default: `30`.
+    pub segment_bonus: usize,
+    /// In `path` mode, points added to every match in the basename. Default: `60`.
+    pub basename_bonus: usize,
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        ScoringConfig {
+            algorithm: Algorithm::default(),
+            case: CaseMode::default(),
+            position_weight: 100,
+            position_decay: PositionDecay::Hyperbolic(8),
+            consecutive_bonus: 10,
+            gap_penalty: 0,
+            exact_bonus: 200,
+            folded_exact_bonus: 150,
+            prefix_bonus: 80,
+            word_prefix_bonus: 70,
+            substring_bonus: 60,
+            char_score: 200,
+            max_score: 100,
+            start_bonus: 50,
+            boundary_bonus: 50,
+            camel_bonus: 40,
+            tokenize: false,
+            overlap_penalty: 5,
+            max_typos: 0,
+            tie_break: TieBreak::Index,
+            path: false,
+            segment_bonus: 30,
+            basename_bonus: 60,
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use std::fmt;
+
+use crate::Tier;
+
+/// A breakdown of how a score was computed, as returned by [`explain`](crate::explain).
+///
+/// The raw score is the sum of the contributions of every matched character.
+/// The bonus of the match's tier is added to it, and the total is scaled so that
+/// `max_raw` maps to `config.max_score`.
+///
+/// # Examples
+///
+/// ```
+/// let explanation = matchr::explain("gc", "git commit").unwrap();
+/// let raw: usize = explanation.chars.iter().map(|c| c.position_weight + c.boundary_bonus).sum();
+/// assert_eq!(explanation.raw, raw);
+/// assert!(explanation.to_string().contains("'c' at 4"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Explanation {
+    /// The query that was scored.
+    pub query: String,
+    /// The candidate it was scored against.
+    pub candidate: String,
+    /// Contribution of every matched character, in the order they were scored.
+    pub chars: Vec<CharScore>,
+    /// Running score after the last matched character.
+    pub raw: usize,
+    /// How closely the match follows the query.
+    pub tier: Tier,
+    /// Points added for the tier.
+    pub bonus: usize,
+    /// Raw points that map to `config.max_score`.
+    pub max_raw: usize,
+    /// The total of the raw score and bonus, scaled onto `0..=config.max_score`.
+    pub normalized: usize,
+    /// Number of typos the match needed.
+    pub typos: usize,
+    /// The final score, as returned by [`score`](crate::score).
+    ///
+    /// Equals `normalized`, unless typos are tolerated or the query is tokenized.
+    pub score: usize,
+    /// In `tokenize` mode, the breakdown of every query term; `score` is their
+    /// average minus the overlap penalty. For a `path` mode query containing
+    /// separators, the breakdown of every query segment against the path segment
+    /// it matched; `score` is their average, kept below `config.max_score`
+    /// unless the candidate equals the query.
+    pub terms: Vec<Explanation>,
+}
+
+/// The contribution of one matched character to the raw score.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CharScore {
+    /// The candidate character.
+    pub ch: char,
+    /// Char index of the character in the candidate.
+    pub position: usize,
+    /// Byte offset of the character in the candidate.
+    pub byte_position: usize,
+    /// Weight of the position, after `config.position_decay`.
+    pub position_weight: usize,
+    /// Start, word-boundary or camelCase bonus.
+    pub boundary_bonus: usize,
+    /// Points added because the character directly follows the previous match.
+    pub consecutive_bonus: usize,
+    /// Points removed for the characters skipped since the previous match.
+    pub gap_penalty: usize,
+    /// Running score after this character.
+    pub running: usize,
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:?} in {:?}: score {}",
+            self.query, self.candidate, self.score
+        )?;
+        if !self.terms.is_empty() {
+            for term in &self.terms {
+                for line in term.to_string().lines() {
+                    writeln!(f, "  {line}")?;
+                }
+            }
+            return write!(
+                f,
+                "  average of terms, less any overlap penalty => {}",
+                self.score
+            );
+        }
+
+        for c in &self.chars {
+            writeln!(
+                f,
+                "  {:?} at {}: weight {}, boundary +{}, consecutive +{}, gap -{} => {}",
+                c.ch,
+                c.position,
+                c.position_weight,
+                c.boundary_bonus,
+                c.consecutive_bonus,
+                c.gap_penalty,
+                c.running
+            )?;
+        }
+        write!(
+            f,
+            "  raw {} + {} bonus {} of {} => {}",
+            self.raw, self.tier, self.bonus, self.max_raw, self.normalized
+        )?;
+        if self.score != self.normalized {
+            write!(
+                f,
+                "\n  {} typo(s), rescaled for typo tolerance => {}",
+                self.typos, self.score
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{explain, explain_with, score_with, Algorithm, ScoringConfig};
+
+    #[test]
+    fn test_explanation_adds_up() {
+        for algorithm in [Algorithm::Greedy, Algorithm::Optimal] {
+            let config = ScoringConfig {
+                algorithm,
+                gap_penalty: 5,
+                prefix_bonus: 20,
+                ..Default::default()
+            };
+            for (query, candi) in [
+                ("gc", "git commit"),
+                ("aab", "axaxab"),
+                ("xb", "xbps"),
+                ("ab", "ab"),
+            ] {
+                let e = explain_with(query, candi, &config).unwrap();
+                assert_eq!(
+                    e.score,
+                    score_with(query, candi, &config),
+                    "{query} / {candi}"
+                );
+                assert_eq!(e.normalized, e.score);
+                let mut running = 0;
+                for c in &e.chars {
+                    running += c.position_weight + c.boundary_bonus + c.consecutive_bonus;
+                    running -= c.gap_penalty;
+                    assert_eq!(c.running, running);
+                }
+                assert_eq!(e.raw, running);
+            }
+        }
+        assert!(explain("xyz", "xbps").is_none());
+    }
+
+    #[test]
+    fn test_explanation_display() {
+        let e = explain("gc", "git commit").unwrap();
+        let text = e.to_string();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[0],
+            format!("\"gc\" in \"git commit\": score {}", e.score)
+        );
+        assert_eq!(
+            lines[1],
+            "  'g' at 0: weight 100, boundary +50, consecutive +0, gap -0 => 150"
+        );
+        assert!(lines[3].ends_with(&format!("of 400 => {}", e.score)));
+    }
+
+    #[test]
+    fn test_explanation_of_terms_and_typos() {
+        let config = ScoringConfig {
+            tokenize: true,
+            max_typos: 1,
+            ..Default::default()
+        };
+        let e = explain_with("push gti", "git push", &config).unwrap();
+        assert_eq!(e.terms.len(), 2);
+        assert_eq!(e.typos, 1);
+        assert_eq!(e.terms[1].typos, 1);
+        assert!(e.terms[1].score < e.terms[1].normalized);
+        assert!(e.to_string().contains("rescaled for typo tolerance"));
+    }
+}
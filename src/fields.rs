@@ -0,0 +1,322 @@
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+
+/// How a line is split into fields.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Delimiter {
+    /// Runs of whitespace, like awk; leading whitespace belongs to no field.
+    #[default]
+    Whitespace,
+    /// Every occurrence of the string, like `cut -d`.
+    Str(String),
+}
+
+/// A range of 1-based field indices; negative indices count from the last field.
+///
+/// `None` leaves that side open, so `FieldRange { start: Some(2), end: None }`
+/// is `2..`, every field from the second on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldRange {
+    /// First field of the range, or `None` to start at the first field.
+    pub start: Option<isize>,
+    /// Last field of the range, inclusive, or `None` to end at the last field.
+    pub end: Option<isize>,
+}
+
+/// Error returned when a field specification is malformed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldError {
+    /// A part of the specification that is not `N`, `N..`, `..M` or `N..M`.
+    InvalidRange(String),
+    /// Field 0; fields are numbered from 1 (or from -1 for the last field).
+    ZeroIndex,
+    /// An empty string delimiter.
+    EmptyDelimiter,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldError::InvalidRange(range) => write!(f, "`{range}` is not a field range"),
+            FieldError::ZeroIndex => write!(f, "fields are numbered from 1"),
+            FieldError::EmptyDelimiter => write!(f, "the delimiter is empty"),
+        }
+    }
+}
+
+impl Error for FieldError {}
+
+/// A selection of fields of a line, in fzf's `--nth` / `--with-nth` syntax.
+///
+/// Matching only the selected fields while keeping the whole line is a matter
+/// of using [`Fields::select`] as the key of [`filter_by_key`](crate::filter_by_key).
+///
+/// Every field keeps the delimiter that follows it, so selecting several
+/// adjacent fields yields the original text between them, column alignment
+/// included. The trailing delimiter of the selection is dropped.
+///
+/// # Examples
+///
+/// ```
+/// use matchr::{filter_by_key, Delimiter, Fields, MatchOptions};
+///
+/// let log = ["a1b2c3 fix typo in readme", "d4e5f6 add ranking"];
+/// // Match the commit message, not the hash.
+/// let message = Fields::parse("2..", Delimiter::Whitespace).unwrap();
+/// let hits = filter_by_key("ad", &log, |line| message.select(line), &MatchOptions::default());
+/// assert_eq!(hits[0].0, &"d4e5f6 add ranking");
+///
+/// let columns = Fields::parse("1,-1", Delimiter::Str(":".into())).unwrap();
+/// assert_eq!(columns.select("root:x:0:0:/root:/bin/sh"), "root:/bin/sh");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Fields {
+    /// How the line is split into fields.
+    pub delimiter: Delimiter,
+    /// Selected ranges, in output order; empty selects the whole line.
+    pub ranges: Vec<FieldRange>,
+}
+
+impl Fields {
+    /// Parses a comma-separated list of field ranges such as `1`, `-1`, `2..`,
+    /// `..3`, `2..-2` or `1,3..`.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - The field ranges.
+    /// * `delimiter` - How lines are split into fields.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`FieldError`] if a range is malformed or the delimiter is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use matchr::{Delimiter, FieldRange, Fields};
+    ///
+    /// let fields = Fields::parse("2..-2", Delimiter::Whitespace).unwrap();
+    /// assert_eq!(fields.ranges, [FieldRange { start: Some(2), end: Some(-2) }]);
+    /// assert!(Fields::parse("0", Delimiter::Whitespace).is_err());
+    /// ```
+    pub fn parse(spec: &str, delimiter: Delimiter) -> Result<Self, FieldError> {
+        if delimiter == Delimiter::Str(String::new()) {
+            return Err(FieldError::EmptyDelimiter);
+        }
+        let ranges = spec.split(',').map(parse_range).collect::<Result<_, _>>()?;
+        Ok(Fields { delimiter, ranges })
+    }
+
+    /// Returns the byte ranges of the selected fields of `line`, in selection order.
+    ///
+    /// Each range covers the field and the delimiter after it, except the last
+    /// one, which stops before its delimiter. Fields out of bounds are skipped.
+    pub fn spans(&self, line: &str) -> Vec<Range<usize>> {
+        let fields = split(line, &self.delimiter);
+        let mut spans: Vec<Range<usize>> = if self.ranges.is_empty() {
+            fields.iter().map(|field| field.start..field.end).collect()
+        } else {
+            let count = fields.len() as isize;
+            let index = |i: isize| if i < 0 { count + 1 + i } else { i };
+            let mut spans = Vec::new();
+            for range in &self.ranges {
+                let start = range.start.map_or(1, index).max(1);
+                let end = range.end.map_or(count, index).min(count);
+                for i in start..=end {
+                    let field = &fields[i as usize - 1];
+                    spans.push(field.start..field.end);
+                }
+            }
+            spans
+        };
+        if let Some(last) = spans.last_mut() {
+            let field = fields.iter().find(|field| field.start == last.start);
+            last.end = field.map_or(last.end, |field| field.content_end);
+        }
+        spans
+    }
+
+    /// Returns the selected fields of `line`, borrowed when they are adjacent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use matchr::{Delimiter, Fields};
+    ///
+    /// let fields = Fields::parse("2,4", Delimiter::Whitespace).unwrap();
+    /// assert_eq!(fields.select("  1 root  sshd  -D"), "root  -D");
+    /// ```
+    pub fn select<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        let spans = self.spans(line);
+        match spans.as_slice() {
+            [] => Cow::Borrowed(""),
+            [first, .., last] if spans.windows(2).all(|w| w[0].end == w[1].start) => {
+                Cow::Borrowed(&line[first.start..last.end])
+            }
+            [only] => Cow::Borrowed(&line[only.clone()]),
+            _ => Cow::Owned(spans.into_iter().map(|span| &line[span]).collect()),
+        }
+    }
+
+    /// Maps char positions in [`Fields::select`]'s output back to char positions
+    /// in `line`, e.g. to highlight a match of the selected fields in the whole line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use matchr::{score_with_positions, Delimiter, Fields};
+    ///
+    /// let line = "a1b2c3 add ranking";
+    /// let message = Fields::parse("2..", Delimiter::Whitespace).unwrap();
+    /// let found = score_with_positions("ar", &message.select(line)).unwrap();
+    /// assert_eq!(message.map_positions(line, &found.positions), [7, 11]);
+    /// ```
+    pub fn map_positions(&self, line: &str, positions: &[usize]) -> Vec<usize> {
+        let mut offsets = Vec::new();
+        for span in self.spans(line) {
+            let start = line[..span.start].chars().count();
+            offsets.extend(start..start + line[span].chars().count());
+        }
+        positions
+            .iter()
+            .filter_map(|&pos| offsets.get(pos).copied())
+            .collect()
+    }
+}
+
+fn parse_range(part: &str) -> Result<FieldRange, FieldError> {
+    let invalid = || FieldError::InvalidRange(part.to_string());
+    let index = |text: &str| -> Result<Option<isize>, FieldError> {
+        if text.is_empty() {
+            return Ok(None);
+        }
+        match text.parse() {
+            Ok(0) => Err(FieldError::ZeroIndex),
+            Ok(i) => Ok(Some(i)),
+            Err(_) => Err(invalid()),
+        }
+    };
+    match part.split_once("..") {
+        Some((start, end)) => Ok(FieldRange {
+            start: index(start)?,
+            end: index(end)?,
+        }),
+        None if part.is_empty() => Err(invalid()),
+        None => {
+            let i = index(part)?;
+            Ok(FieldRange { start: i, end: i })
+        }
+    }
+}
+
+/// A field of a line: its text is `start..content_end`, followed by its
+/// delimiter up to `end`.
+struct Field {
+    start: usize,
+    content_end: usize,
+    end: usize,
+}
+
+fn split(line: &str, delimiter: &Delimiter) -> Vec<Field> {
+    let mut fields = Vec::new();
+    match delimiter {
+        Delimiter::Whitespace => {
+            let mut rest = line.trim_start();
+            while !rest.is_empty() {
+                let start = line.len() - rest.len();
+                let content = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                rest = rest[content..].trim_start();
+                fields.push(Field {
+                    start,
+                    content_end: start + content,
+                    end: line.len() - rest.len(),
+                });
+            }
+        }
+        Delimiter::Str(delimiter) => {
+            let mut start = 0;
+            for (at, _) in line.match_indices(delimiter.as_str()) {
+                fields.push(Field {
+                    start,
+                    content_end: at,
+                    end: at + delimiter.len(),
+                });
+                start = at + delimiter.len();
+            }
+            fields.push(Field {
+                start,
+                content_end: line.len(),
+                end: line.len(),
+            });
+        }
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(spec: &str) -> Fields {
+        Fields::parse(spec, Delimiter::Whitespace).unwrap()
+    }
+
+    #[test]
+    fn test_parse_ranges() {
+        let range = |start, end| FieldRange { start, end };
+        assert_eq!(
+            fields("1,-1,2..,..3,2..-2,..").ranges,
+            [
+                range(Some(1), Some(1)),
+                range(Some(-1), Some(-1)),
+                range(Some(2), None),
+                range(None, Some(3)),
+                range(Some(2), Some(-2)),
+                range(None, None),
+            ]
+        );
+        let parse = |spec| Fields::parse(spec, Delimiter::Whitespace);
+        assert_eq!(parse("0"), Err(FieldError::ZeroIndex));
+        assert_eq!(parse("1,"), Err(FieldError::InvalidRange(String::new())));
+        assert_eq!(parse("a..2"), Err(FieldError::InvalidRange("a..2".into())));
+        assert_eq!(
+            Fields::parse("1", Delimiter::Str(String::new())),
+            Err(FieldError::EmptyDelimiter)
+        );
+    }
+
+    #[test]
+    fn test_select_whitespace_fields() {
+        let line = "  root   42  0.0 /usr/bin/sshd -D";
+        assert_eq!(fields("1").select(line), "root");
+        assert_eq!(fields("2..3").select(line), "42  0.0");
+        assert_eq!(fields("-2..").select(line), "/usr/bin/sshd -D");
+        assert_eq!(fields("3,1").select(line), "0.0 root");
+        assert_eq!(fields("..").select(line), line.trim_start());
+        assert_eq!(Fields::default().select(line), line.trim_start());
+        assert_eq!(fields("9").select(line), "");
+        assert_eq!(fields("3..2").select(line), "");
+        assert!(matches!(fields("2..3").select(line), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_select_delimited_fields() {
+        let csv = |spec| Fields::parse(spec, Delimiter::Str(", ".into())).unwrap();
+        let line = "Ada, , Lovelace";
+        assert_eq!(csv("2").select(line), "");
+        assert_eq!(csv("3").select(line), "Lovelace");
+        assert_eq!(csv("1,3").select(line), "Ada, Lovelace");
+        assert_eq!(csv("-3..-2").select(line), "Ada, ");
+        assert_eq!(csv("1").select(""), "");
+    }
+
+    #[test]
+    fn test_map_positions() {
+        let line = "é1 b2 c3 d4";
+        let selected = fields("3,1");
+        assert_eq!(selected.select(line), "c3 é1");
+        assert_eq!(selected.map_positions(line, &[0, 3, 4, 9]), [6, 0, 1]);
+    }
+}
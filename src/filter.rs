@@ -0,0 +1,353 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+use crate::{Matcher, ScoringConfig, TieBreak};
+
+/// Options for [`filter_items`] and [`filter_by_key`].
+///
+/// Unlike [`match_items`](crate::match_items), the filtering functions never
+/// return items the query does not match.
+///
+/// # Examples
+///
+/// ```
+/// use matchr::MatchOptions;
+///
+/// let picker = MatchOptions {
+///     min_score: 20,
+///     limit: Some(20),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MatchOptions {
+    /// How each item is scored.
+    pub config: ScoringConfig,
+    /// Lowest score an item needs to be returned. Default: `0`, every match.
+    pub min_score: usize,
+    /// Maximum number of items returned. Default: `None`, no limit.
+    pub limit: Option<usize>,
+}
+
+/// Matches `items` against the `query` and returns only the matching items
+/// that reach `options.min_score`, best first, at most `options.limit` of them.
+///
+/// With a limit of k, only the k best items are kept in a bounded heap and
+/// sorted, so picking 20 rows out of a huge list costs O(n log k).
+///
+/// # Arguments
+///
+/// * `query` - The search query string slice.
+/// * `items` - Slice of string slices to be matched.
+/// * `options` - The scoring options, threshold and limit.
+///
+/// # Returns
+///
+/// A vector of tuples `(item, score)`, sorted by descending score. Items with
+/// equal scores are ranked by their unnormalized score, then by `options.config.tie_break`, input order by default.
+///
+/// # Examples
+///
+/// ```
+/// use matchr::MatchOptions;
+///
+/// let items = ["xbps-install", "grep", "xbps-remove", "xbps-query"];
+/// let options = MatchOptions {
+///     limit: Some(2),
+///     ..Default::default()
+/// };
+/// let results = matchr::filter_items("xb", &items, &options);
+/// assert_eq!(results.len(), 2);
+/// assert!(results.iter().all(|(item, _)| item.starts_with("xbps")));
+/// ```
+pub fn filter_items<'a>(
+    query: &str,
+    items: &[&'a str],
+    options: &MatchOptions,
+) -> Vec<(&'a str, usize)> {
+    filter_by_key(query, items, |item| *item, options)
+        .into_iter()
+        .map(|(item, score)| (*item, score))
+        .collect()
+}
+
+/// Matches any kind of `items` against the `query`, using `key` to get the
+/// string to match from each item, and returns only the matching items that
+/// reach `options.min_score`, best first, at most `options.limit` of them.
+///
+/// # Arguments
+///
+/// * `query` - The search query string slice.
+/// * `items` - Slice of items to be matched.
+/// * `key` - Returns the string to match for an item.
+/// * `options` - The scoring options, threshold and limit.
+///
+/// # Returns
+///
+/// A vector of tuples `(item, score)` borrowing from `items`, sorted by descending
+/// score. Items with equal scores are ranked by their unnormalized score, then by `options.config.tie_break`.
+pub fn filter_by_key<'a, T, K, F>(
+    query: &str,
+    items: &'a [T],
+    mut key: F,
+    options: &MatchOptions,
+) -> Vec<(&'a T, usize)>
+where
+    F: FnMut(&'a T) -> K,
+    K: AsRef<str>,
+{
+    let mut matcher = Matcher::with_config(query, options.config);
+    let scored = items.iter().filter_map(|item| {
+        let candi = key(item);
+        let score = matcher.try_score(candi.as_ref())?;
+        Some((item, score, Tie::new(&matcher, candi.as_ref(), true)))
+    });
+    select(scored, options)
+}
+
+/// Keeps the items of `scored` that reach `options.min_score` and returns the
+/// best `options.limit` of them, ordered by descending score, then unnormalized
+/// score, then `options.config.tie_break`, then input order.
+pub(crate) fn select<T>(
+    scored: impl IntoIterator<Item = (T, usize, Tie)>,
+    options: &MatchOptions,
+) -> Vec<(T, usize)> {
+    let tie_break = options.config.tie_break;
+    let scored = scored
+        .into_iter()
+        .enumerate()
+        .filter(|(_, (_, score, _))| *score >= options.min_score)
+        .map(|(index, (item, score, tie))| Ranked {
+            score,
+            tie: Tie { index, ..tie },
+            tie_break,
+            item,
+        });
+
+    let mut ranked: Vec<_> = match options.limit {
+        None => scored.collect(),
+        Some(0) => Vec::new(),
+        Some(limit) => {
+            // Min-heap of the best `limit` items seen so far.
+            let mut heap = BinaryHeap::with_capacity(limit + 1);
+            for entry in scored {
+                if heap.len() < limit {
+                    heap.push(Reverse(entry));
+                } else if heap.peek().is_some_and(|Reverse(worst)| entry > *worst) {
+                    heap.pop();
+                    heap.push(Reverse(entry));
+                }
+            }
+            heap.into_iter().map(|Reverse(entry)| entry).collect()
+        }
+    };
+
+    ranked.sort_unstable_by(|a, b| b.cmp(a));
+    ranked
+        .into_iter()
+        .map(|entry| (entry.item, entry.score))
+        .collect()
+}
+
+/// The keys an item is compared on when its score ties with another item:
+/// first its unnormalized score, then the keys of the tie-break policy.
+///
+/// Keys the tie-break policy does not use are left empty.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(crate) struct Tie {
+    raw: usize,
+    index: usize,
+    len: usize,
+    begin: usize,
+    text: String,
+}
+
+impl Tie {
+    /// Records the keys of `candi`, which was just scored by `matcher`.
+    pub(crate) fn new(matcher: &Matcher, candi: &str, matched: bool) -> Self {
+        let tie_break = matcher.config().tie_break;
+        let mut tie = Tie::of_text(tie_break, candi);
+        if matched {
+            tie.raw = matcher.raw();
+        }
+        if tie_break.uses(TieBreak::Begin) && matched {
+            let first = matcher.positions().iter().min();
+            tie.begin = first.copied().unwrap_or(usize::MAX);
+        }
+        tie
+    }
+
+    /// Records the keys of `candi` that do not depend on where a query matched it.
+    pub(crate) fn of_text(tie_break: TieBreak, candi: &str) -> Self {
+        let mut tie = Tie::default();
+        if tie_break.uses(TieBreak::Length) {
+            tie.len = candi.chars().count();
+        }
+        if tie_break.uses(TieBreak::Begin) {
+            tie.begin = usize::MAX;
+        }
+        if tie_break.uses(TieBreak::Lexical) {
+            tie.text = candi.to_string();
+        }
+        tie
+    }
+}
+
+/// Orders `a` before `b` (`Less`) if `tie_break` ranks it first.
+fn compare(tie_break: TieBreak, a: &Tie, b: &Tie) -> Ordering {
+    match tie_break {
+        TieBreak::Index => a.index.cmp(&b.index),
+        TieBreak::Length => a.len.cmp(&b.len),
+        TieBreak::Begin => a.begin.cmp(&b.begin),
+        TieBreak::Lexical => a.text.cmp(&b.text),
+        TieBreak::Chain(policies) => policies.iter().fold(Ordering::Equal, |order, &policy| {
+            order.then_with(|| compare(policy, a, b))
+        }),
+    }
+}
+
+/// A scored item, ordered so that greater means ranked first.
+struct Ranked<T> {
+    score: usize,
+    tie: Tie,
+    tie_break: TieBreak,
+    item: T,
+}
+
+impl<T> PartialEq for Ranked<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Ranked<T> {}
+
+impl<T> PartialOrd for Ranked<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Ranked<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .cmp(&other.score)
+            .then_with(|| self.tie.raw.cmp(&other.tie.raw))
+            .then_with(|| compare(self.tie_break, &other.tie, &self.tie))
+            .then_with(|| other.tie.index.cmp(&self.tie.index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{match_items, match_items_with, par_filter_items, PositionDecay};
+
+    const ITEMS: [&str; 10] = [
+        "xbps-install",
+        "grep",
+        "xbps-remove",
+        "xargs-b",
+        "bash",
+        "xbps-query",
+        "xbps-query",
+        "box",
+        "find",
+        "xb",
+    ];
+
+    #[test]
+    fn test_filter_equals_filtered_match_items() {
+        for limit in [None, Some(0), Some(1), Some(3), Some(100)] {
+            for min_score in [0, 40, 101] {
+                let options = MatchOptions {
+                    min_score,
+                    limit,
+                    ..Default::default()
+                };
+                let expected: Vec<_> = match_items("xb", &ITEMS)
+                    .into_iter()
+                    .filter(|(_, score)| *score > 0 && *score >= min_score)
+                    .take(limit.unwrap_or(usize::MAX))
+                    .collect();
+                assert_eq!(filter_items("xb", &ITEMS, &options), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_filter_excludes_non_matches() {
+        let results = filter_items("xb", &ITEMS, &MatchOptions::default());
+        assert!(results
+            .iter()
+            .all(|(item, _)| *item != "grep" && *item != "find"));
+        assert_eq!(results[0].0, "xb");
+        assert!(filter_items("", &ITEMS, &MatchOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_tie_break_policies() {
+        // Every position weighs the same and there are no boundary bonuses, so
+        // all items tie on "ab".
+        let flat = ScoringConfig {
+            position_decay: PositionDecay::Linear(0),
+            start_bonus: 0,
+            boundary_bonus: 0,
+            ..Default::default()
+        };
+        let items = ["xxabz", "xaby", "xxxab", "xabyy", "xaby"];
+        let cases: [(TieBreak, [&str; 5]); 5] = [
+            (TieBreak::Index, items),
+            (
+                TieBreak::Length,
+                ["xaby", "xaby", "xxabz", "xxxab", "xabyy"],
+            ),
+            (TieBreak::Begin, ["xaby", "xabyy", "xaby", "xxabz", "xxxab"]),
+            (
+                TieBreak::Lexical,
+                ["xaby", "xaby", "xabyy", "xxabz", "xxxab"],
+            ),
+            (
+                TieBreak::Chain(&[TieBreak::Begin, TieBreak::Length]),
+                ["xaby", "xaby", "xabyy", "xxabz", "xxxab"],
+            ),
+        ];
+        for (tie_break, expected) in cases {
+            let config = ScoringConfig { tie_break, ..flat };
+            let ranked: Vec<_> = match_items_with("ab", &items, &config)
+                .into_iter()
+                .map(|(item, _)| item)
+                .collect();
+            assert_eq!(ranked, expected, "{tie_break:?}");
+
+            for limit in [None, Some(2)] {
+                let options = MatchOptions {
+                    config,
+                    limit,
+                    ..Default::default()
+                };
+                let filtered: Vec<_> = filter_items("ab", &items, &options)
+                    .into_iter()
+                    .map(|(item, _)| item)
+                    .collect();
+                assert_eq!(filtered, expected[..limit.unwrap_or(5)], "{tie_break:?}");
+                assert_eq!(
+                    par_filter_items("ab", &items, &options),
+                    filter_items("ab", &items, &options)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_tie_break_only_orders_equal_scores() {
+        let config = ScoringConfig {
+            tie_break: TieBreak::Length,
+            ..Default::default()
+        };
+        let results = match_items_with("xb", &["xxxb", "xbps-install", "grep", "ab"], &config);
+        assert_eq!(results[0].0, "xbps-install");
+        // Non-matches tie at 0 and are ordered too.
+        assert_eq!(results[2..], [("ab", 0), ("grep", 0)]);
+    }
+}
@@ -0,0 +1,248 @@
+/// A run of consecutive candidate characters that are all matched or all unmatched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span<'a> {
+    /// The text of the run.
+    pub text: &'a str,
+    /// Whether the characters of the run are matched by the query.
+    pub matched: bool,
+    /// Char index in the candidate of the first character of the run.
+    pub position: usize,
+}
+
+/// ANSI escape sequences wrapped around every matched run by [`highlight_ansi`].
+///
+/// The default, bold green, and the provided styles only turn off what they
+/// turned on, so they can be nested in a line that has its own style.
+///
+/// # Examples
+///
+/// ```
+/// use matchr::AnsiStyle;
+///
+/// let red_underline = AnsiStyle {
+///     start: "\x1b[4;31m",
+///     end: "\x1b[24;39m",
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnsiStyle {
+    /// Written before a matched run.
+    pub start: &'static str,
+    /// Written after a matched run.
+    pub end: &'static str,
+}
+
+impl AnsiStyle {
+    /// Bold.
+    pub const BOLD: AnsiStyle = AnsiStyle {
+        start: "\x1b[1m",
+        end: "\x1b[22m",
+    };
+    /// Underlined.
+    pub const UNDERLINE: AnsiStyle = AnsiStyle {
+        start: "\x1b[4m",
+        end: "\x1b[24m",
+    };
+    /// Reverse video.
+    pub const REVERSE: AnsiStyle = AnsiStyle {
+        start: "\x1b[7m",
+        end: "\x1b[27m",
+    };
+}
+
+impl Default for AnsiStyle {
+    fn default() -> Self {
+        AnsiStyle {
+            start: "\x1b[1;32m",
+            end: "\x1b[22;39m",
+        }
+    }
+}
+
+/// Splits `candi` into runs of matched and unmatched characters.
+///
+/// # Arguments
+///
+/// * `candi` - The candidate string slice.
+/// * `positions` - Char indices of the matched characters, as in
+///   [`Match::positions`](crate::Match::positions), in any order. Indices past
+///   the end of `candi` are ignored.
+///
+/// # Returns
+///
+/// The runs in candidate order; adjacent matched characters share one run.
+///
+/// # Examples
+///
+/// ```
+/// let found = matchr::score_with_positions("xbi", "xbps-install").unwrap();
+/// let runs: Vec<_> = matchr::highlight_spans("xbps-install", &found.positions)
+///     .iter()
+///     .map(|span| (span.text, span.matched))
+///     .collect();
+/// assert_eq!(runs, [("xb", true), ("ps-", false), ("i", true), ("nstall", false)]);
+/// ```
+pub fn highlight_spans<'a>(candi: &'a str, positions: &[usize]) -> Vec<Span<'a>> {
+    let mut positions = positions.to_vec();
+    positions.sort_unstable();
+    let mut positions = positions.into_iter().peekable();
+
+    let mut spans: Vec<Span<'a>> = Vec::new();
+    let mut start = 0;
+    for (pos, (byte, _)) in candi.char_indices().enumerate() {
+        while positions.next_if(|&p| p < pos).is_some() {}
+        let matched = positions.next_if_eq(&pos).is_some();
+        match spans.last_mut() {
+            Some(last) if last.matched == matched => {}
+            _ => {
+                if let Some(last) = spans.last_mut() {
+                    last.text = &candi[start..byte];
+                }
+                spans.push(Span {
+                    text: "",
+                    matched,
+                    position: pos,
+                });
+                start = byte;
+            }
+        }
+    }
+    if let Some(last) = spans.last_mut() {
+        last.text = &candi[start..];
+    }
+    spans
+}
+
+/// Renders `candi` by passing every run of [`highlight_spans`] to `render`,
+/// along with the output string to append to.
+///
+/// # Examples
+///
+/// ```
+/// let found = matchr::score_with_positions("gc", "git commit").unwrap();
+/// let text = matchr::highlight_with("git commit", &found.positions, |out, span| {
+///     if span.matched {
+///         out.push_str(&span.text.to_uppercase());
+///     } else {
+///         out.push_str(span.text);
+///     }
+/// });
+/// assert_eq!(text, "Git Commit");
+/// ```
+pub fn highlight_with<F>(candi: &str, positions: &[usize], mut render: F) -> String
+where
+    F: FnMut(&mut String, Span<'_>),
+{
+    let mut out = String::with_capacity(candi.len());
+    for span in highlight_spans(candi, positions) {
+        render(&mut out, span);
+    }
+    out
+}
+
+/// Wraps every matched run of `candi` in the escape sequences of `style`.
+///
+/// # Examples
+///
+/// ```
+/// use matchr::AnsiStyle;
+///
+/// let found = matchr::score_with_positions("gc", "git commit").unwrap();
+/// let text = matchr::highlight_ansi("git commit", &found.positions, &AnsiStyle::BOLD);
+/// assert_eq!(text, "\x1b[1mg\x1b[22mit \x1b[1mc\x1b[22mommit");
+/// ```
+pub fn highlight_ansi(candi: &str, positions: &[usize], style: &AnsiStyle) -> String {
+    highlight_with(candi, positions, |out, span| {
+        if span.matched {
+            out.push_str(style.start);
+            out.push_str(span.text);
+            out.push_str(style.end);
+        } else {
+            out.push_str(span.text);
+        }
+    })
+}
+
+/// Escapes `candi` for HTML and wraps every matched run in `<mark>` tags.
+///
+/// # Examples
+///
+/// ```
+/// let found = matchr::score_with_positions("ab", "a<b>").unwrap();
+/// assert_eq!(
+///     matchr::highlight_html("a<b>", &found.positions),
+///     "<mark>a</mark>&lt;<mark>b</mark>&gt;"
+/// );
+/// ```
+pub fn highlight_html(candi: &str, positions: &[usize]) -> String {
+    highlight_with(candi, positions, |out, span| {
+        if span.matched {
+            out.push_str("<mark>");
+        }
+        for c in span.text.chars() {
+            match c {
+                '&' => out.push_str("&amp;"),
+                '<' => out.push_str("&lt;"),
+                '>' => out.push_str("&gt;"),
+                '"' => out.push_str("&quot;"),
+                '\'' => out.push_str("&#39;"),
+                c => out.push(c),
+            }
+        }
+        if span.matched {
+            out.push_str("</mark>");
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spans_merge_runs() {
+        let spans = highlight_spans("héllo wörld", &[6, 1, 2, 3, 7, 99, 2]);
+        let runs: Vec<_> = spans
+            .iter()
+            .map(|s| (s.text, s.matched, s.position))
+            .collect();
+        assert_eq!(
+            runs,
+            [
+                ("h", false, 0),
+                ("éll", true, 1),
+                ("o ", false, 4),
+                ("wö", true, 6),
+                ("rld", false, 8),
+            ]
+        );
+        assert_eq!(
+            highlight_spans("ab", &[]),
+            [Span {
+                text: "ab",
+                matched: false,
+                position: 0
+            }]
+        );
+        assert_eq!(highlight_spans("ab", &[0, 1]).len(), 1);
+        assert!(highlight_spans("", &[0]).is_empty());
+    }
+
+    #[test]
+    fn test_ansi_and_html() {
+        let positions = [0, 1, 4];
+        assert_eq!(
+            highlight_ansi("a&b\"c'", &positions, &AnsiStyle::default()),
+            "\x1b[1;32ma&\x1b[22;39mb\"\x1b[1;32mc\x1b[22;39m'"
+        );
+        assert_eq!(
+            highlight_ansi("a&b\"c'", &positions, &AnsiStyle::UNDERLINE),
+            "\x1b[4ma&\x1b[24mb\"\x1b[4mc\x1b[24m'"
+        );
+        assert_eq!(
+            highlight_html("a&b\"c'", &positions),
+            "<mark>a&amp;</mark>b&quot;<mark>c</mark>&#39;"
+        );
+        assert_eq!(highlight_html("", &[]), "");
+    }
+}
@@ -1,3 +1,31 @@
+mod config;
+mod explain;
+mod fields;
+mod filter;
+mod highlight;
+mod matcher;
+mod parallel;
+#[cfg(feature = "picker")]
+mod picker;
+mod query;
+mod record;
+mod session;
+
+pub use config::{Algorithm, CaseMode, PositionDecay, ScoringConfig, TieBreak};
+pub use explain::{CharScore, Explanation};
+pub use fields::{Delimiter, FieldError, FieldRange, Fields};
+pub use filter::{filter_by_key, filter_items, MatchOptions};
+pub use highlight::{
+    highlight_ansi, highlight_html, highlight_spans, highlight_with, AnsiStyle, Span,
+};
+pub use matcher::{Match, Matcher, Tier};
+pub use parallel::{par_filter_items, par_match_items, par_match_items_with};
+#[cfg(feature = "picker")]
+pub use picker::{Picker, PickerOptions};
+pub use query::{filter_extended, ExtendedQuery, QueryError};
+pub use record::{filter_records, Combine, FieldMatch, RecordField, RecordMatch, RecordMatcher};
+pub use session::Session;
+
 /// Scores how well `query` matches the `candi` string.
 ///
 /// The score is based on whether `query` is a subsequence of `candi`, with additional weighting:
@@ -21,57 +49,129 @@
 /// assert_eq!(score, 100);
 /// ```
 pub fn score(query: &str, candi: &str) -> usize {
-    if query.is_empty() {
-        return 0;
-    }
-
-    if !is_subsequence(query, candi) {
-        return 0;
-    }
-
-    let mut score = 0usize;
-    let mut candi_chars = candi.char_indices();
-    let mut last_pos = None;
-
-    for qc in query.chars() {
-        let mut found = false;
-        while let Some((pos, cc)) = candi_chars.next() {
-            if cc == qc {
-                let pos_score = 10usize.saturating_sub(pos);
-                score += pos_score;
+    score_with(query, candi, &ScoringConfig::default())
+}
 
-                if let Some(lp) = last_pos {
-                    if pos == lp + 1 {
-                        score += score / 10;
-                    }
-                }
+/// Scores how well `query` matches `candi` using the given `config`.
+///
+/// # Arguments
+///
+/// * `query` - The search query string slice.
+/// * `candi` - The candidate string slice to be matched against.
+/// * `config` - The scoring options.
+///
+/// # Returns
+///
+/// A usize score between 0 and `config.max_score`, higher means better match.
+///
+/// # Examples
+///
+/// ```
+/// use matchr::{Algorithm, ScoringConfig};
+///
+/// let config = ScoringConfig {
+///     algorithm: Algorithm::Optimal,
+///     ..Default::default()
+/// };
+/// assert!(matchr::score_with("aab", "axaxab", &config) > matchr::score("aab", "axaxab"));
+/// ```
+pub fn score_with(query: &str, candi: &str, config: &ScoringConfig) -> usize {
+    Matcher::with_config(query, *config).score(candi)
+}
 
-                last_pos = Some(pos);
-                found = true;
-                break;
-            }
-        }
-        if !found {
-            return 0;
-        }
-    }
+/// Scores `query` against `candi` and reports which candidate characters were matched.
+///
+/// The positions are recorded during the same walk that computes the score, so
+/// highlighting them always agrees with the ranking produced by [`score`] and
+/// [`match_items`].
+///
+/// # Arguments
+///
+/// * `query` - The search query string slice.
+/// * `candi` - The candidate string slice to be matched against.
+///
+/// # Returns
+///
+/// `Some(Match)` if `query` is a non-empty subsequence of `candi`, otherwise `None`.
+///
+/// # Examples
+///
+/// ```
+/// let m = matchr::score_with_positions("ft", "feature").unwrap();
+/// assert_eq!(m.positions, vec![0, 3]);
+/// assert_eq!(m.score, matchr::score("ft", "feature"));
+/// ```
+pub fn score_with_positions(query: &str, candi: &str) -> Option<Match> {
+    match_with(query, candi, &ScoringConfig::default())
+}
 
-    if query == candi {
-        return 100;
-    }
-    let max_possible = query.len() * 15;
+/// Scores `query` against `candi` using the given `config` and reports which
+/// candidate characters were matched.
+///
+/// # Arguments
+///
+/// * `query` - The search query string slice.
+/// * `candi` - The candidate string slice to be matched against.
+/// * `config` - The scoring options.
+///
+/// # Returns
+///
+/// `Some(Match)` if `query` is a non-empty subsequence of `candi`, otherwise `None`.
+///
+/// # Examples
+///
+/// ```
+/// use matchr::{Algorithm, ScoringConfig};
+///
+/// let config = ScoringConfig {
+///     algorithm: Algorithm::Optimal,
+///     ..Default::default()
+/// };
+/// let m = matchr::match_with("aab", "axaxab", &config).unwrap();
+/// assert_eq!(m.positions, vec![0, 4, 5]);
+/// ```
+pub fn match_with(query: &str, candi: &str, config: &ScoringConfig) -> Option<Match> {
+    Matcher::with_config(query, *config).find(candi)
+}
 
-    ((score * 100) / max_possible).min(100)
+/// Breaks down how the score of `query` against `candi` is computed.
+///
+/// # Arguments
+///
+/// * `query` - The search query string slice.
+/// * `candi` - The candidate string slice to be matched against.
+///
+/// # Returns
+///
+/// `Some(Explanation)` listing the contribution of every matched character and
+/// the final normalization, or `None` if `query` does not match `candi`.
+///
+/// # Examples
+///
+/// ```
+/// let explanation = matchr::explain("gc", "git commit").unwrap();
+/// assert_eq!(explanation.score, matchr::score("gc", "git commit"));
+/// println!("{explanation}");
+/// ```
+pub fn explain(query: &str, candi: &str) -> Option<Explanation> {
+    explain_with(query, candi, &ScoringConfig::default())
 }
 
-fn is_subsequence(query: &str, candi: &str) -> bool {
-    let mut candi_chars = candi.chars();
-    for qc in query.chars() {
-        if candi_chars.find(|cc| *cc == qc).is_none() {
-            return false;
-        }
-    }
-    true
+/// Breaks down how the score of `query` against `candi` is computed using the
+/// given `config`.
+///
+/// # Arguments
+///
+/// * `query` - The search query string slice.
+/// * `candi` - The candidate string slice to be matched against.
+/// * `config` - The scoring options.
+///
+/// # Returns
+///
+/// `Some(Explanation)` listing the contribution of every matched character and
+/// the final normalization, or `None` if `query` does not match `candi`.
+pub fn explain_with(query: &str, candi: &str, config: &ScoringConfig) -> Option<Explanation> {
+    Matcher::with_config(query, *config).explain(candi)
 }
 
 /// Matches multiple `items` against the `query` and returns
@@ -84,7 +184,8 @@ fn is_subsequence(query: &str, candi: &str) -> bool {
 ///
 /// # Returns
 ///
-/// A vector of tuples `(item, score)`, sorted by descending score.
+/// A vector of tuples `(item, score)`, sorted by descending score. Items with
+/// equal scores are ranked by their unnormalized score, then keep their input order.
 ///
 /// # Examples
 ///
@@ -94,13 +195,134 @@ fn is_subsequence(query: &str, candi: &str) -> bool {
 /// assert_eq!(results[0].0, "fefe");
 /// ```
 pub fn match_items<'a>(query: &str, items: &[&'a str]) -> Vec<(&'a str, usize)> {
-    let mut scored: Vec<_> = items
-        .iter()
-        .map(|item| (*item, score(query, item)))
-        .collect();
-    
-    scored.sort_by(|a, b| b.1.cmp(&a.1));
-    scored
+    match_items_with(query, items, &ScoringConfig::default())
+}
+
+/// Matches multiple `items` against the `query` using the given `config`.
+///
+/// # Arguments
+///
+/// * `query` - The search query string slice.
+/// * `items` - Slice of string slices to be matched.
+/// * `config` - The scoring options.
+///
+/// # Returns
+///
+/// A vector of tuples `(item, score)`, sorted by descending score. Items with
+/// equal scores are ranked by their unnormalized score, then by `config.tie_break`.
+///
+/// # Examples
+///
+/// ```
+/// use matchr::{Algorithm, ScoringConfig};
+///
+/// let config = ScoringConfig {
+///     algorithm: Algorithm::Optimal,
+///     ..Default::default()
+/// };
+/// let results = matchr::match_items_with("fefe", &["feature", "fefe"], &config);
+/// assert_eq!(results[0].0, "fefe");
+/// ```
+pub fn match_items_with<'a>(
+    query: &str,
+    items: &[&'a str],
+    config: &ScoringConfig,
+) -> Vec<(&'a str, usize)> {
+    match_by_key_with(query, items, |item| *item, config)
+        .into_iter()
+        .map(|(item, score)| (*item, score))
+        .collect()
+}
+
+/// Matches any kind of `items` against the `query`, using `key` to get the
+/// string to match from each item.
+///
+/// # Arguments
+///
+/// * `query` - The search query string slice.
+/// * `items` - Slice of items to be matched.
+/// * `key` - Returns the string to match for an item, e.g. `|cmd| cmd.name()`.
+///
+/// # Returns
+///
+/// A vector of tuples `(item, score)` borrowing from `items`, sorted by descending score.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+///
+/// let files = [PathBuf::from("src/lib.rs"), PathBuf::from("Cargo.toml")];
+/// let results = matchr::match_by_key("toml", &files, |path| path.to_string_lossy());
+/// assert_eq!(results[0].0, &files[1]);
+/// ```
+pub fn match_by_key<'a, T, K, F>(query: &str, items: &'a [T], key: F) -> Vec<(&'a T, usize)>
+where
+    F: FnMut(&'a T) -> K,
+    K: AsRef<str>,
+{
+    match_by_key_with(query, items, key, &ScoringConfig::default())
+}
+
+/// Matches any kind of `items` against the `query` using the given `config`,
+/// using `key` to get the string to match from each item.
+///
+/// # Arguments
+///
+/// * `query` - The search query string slice.
+/// * `items` - Slice of items to be matched.
+/// * `key` - Returns the string to match for an item.
+/// * `config` - The scoring options.
+///
+/// # Returns
+///
+/// A vector of tuples `(item, score)` borrowing from `items`, sorted by descending
+/// score. Items with equal scores are ranked by their unnormalized score, then by `config.tie_break`.
+pub fn match_by_key_with<'a, T, K, F>(
+    query: &str,
+    items: &'a [T],
+    mut key: F,
+    config: &ScoringConfig,
+) -> Vec<(&'a T, usize)>
+where
+    F: FnMut(&'a T) -> K,
+    K: AsRef<str>,
+{
+    let mut matcher = Matcher::with_config(query, *config);
+    let scored = items.iter().map(|item| {
+        let candi = key(item);
+        let score = matcher.try_score(candi.as_ref());
+        let tie = filter::Tie::new(&matcher, candi.as_ref(), score.is_some());
+        (item, score.unwrap_or(0), tie)
+    });
+    let options = MatchOptions {
+        config: *config,
+        ..Default::default()
+    };
+    filter::select(scored, &options)
+}
+
+/// Matches `items` of any string-like type (`String`, `Box<str>`, `Cow<str>`, ...)
+/// against the `query`.
+///
+/// # Arguments
+///
+/// * `query` - The search query string slice.
+/// * `items` - Slice of string-like items to be matched.
+///
+/// # Returns
+///
+/// A vector of tuples `(item, score)` borrowing from `items`, sorted by descending score.
+///
+/// # Examples
+///
+/// ```
+/// let items = vec![String::from("feature"), String::from("fefe")];
+/// let results = matchr::match_as_ref("fefe", &items);
+/// assert_eq!(results[0].0, "fefe");
+/// ```
+pub fn match_as_ref<'a, T: AsRef<str>>(query: &str, items: &'a [T]) -> Vec<(&'a T, usize)> {
+    match_by_key(query, items, |item| item.as_ref())
 }
 
 #[cfg(test)]
@@ -166,4 +388,382 @@ mod tests {
             println!("{} => score: {}", item, score);
         }
     }
+
+    #[test]
+    fn test_positions_agree_with_score() {
+        let candidates = ["xbps-install", "feature", "fefe", "a_xab", "grep"];
+        for candi in candidates {
+            match score_with_positions("fe", candi) {
+                Some(m) => {
+                    assert_eq!(m.score, score("fe", candi));
+                    for (&cp, &bp) in m.positions.iter().zip(&m.byte_positions) {
+                        assert_eq!(candi.chars().nth(cp), candi[bp..].chars().next());
+                    }
+                }
+                None => assert_eq!(score("fe", candi), 0),
+            }
+        }
+        assert!(score_with_positions("", "fefe").is_none());
+        assert!(score_with_positions("zz", "fefe").is_none());
+    }
+
+    #[test]
+    fn test_optimal_never_worse_than_greedy() {
+        let optimal = ScoringConfig {
+            algorithm: Algorithm::Optimal,
+            ..Default::default()
+        };
+        let pairs = [
+            ("ab", "a_xab"),
+            ("aab", "a_aab"),
+            ("xb", "xbps-install"),
+            ("fefe", "feature-fefe"),
+            ("gc", "git commit"),
+            ("zz", "fefe"),
+        ];
+        for (query, candi) in pairs {
+            assert!(score_with(query, candi, &optimal) >= score(query, candi));
+            let m = match_with(query, candi, &optimal);
+            assert_eq!(
+                m.as_ref().map_or(0, |m| m.score),
+                score_with(query, candi, &optimal)
+            );
+            assert_eq!(m.is_some(), score_with_positions(query, candi).is_some());
+        }
+    }
+
+    #[test]
+    fn test_optimal_prefers_consecutive_run() {
+        let optimal = ScoringConfig {
+            algorithm: Algorithm::Optimal,
+            ..Default::default()
+        };
+        let greedy = score_with_positions("aab", "axaxab").unwrap();
+        let best = match_with("aab", "axaxab", &optimal).unwrap();
+        assert_eq!(greedy.positions, vec![0, 2, 5]);
+        assert_eq!(best.positions, vec![0, 4, 5]);
+        assert!(best.score > greedy.score);
+    }
+
+    #[test]
+    fn test_case_modes() {
+        let config = |case| ScoringConfig {
+            case,
+            ..Default::default()
+        };
+        assert_eq!(score("cargo", "Cargo.toml"), 0);
+        assert!(score_with("cargo", "Cargo.toml", &config(CaseMode::Insensitive)) > 0);
+        assert!(score_with("cargo", "Cargo.toml", &config(CaseMode::Smart)) > 0);
+        assert_eq!(
+            score_with("Cargo", "cargo.toml", &config(CaseMode::Smart)),
+            0
+        );
+        assert_eq!(
+            score_with("CARGO", "cargo", &config(CaseMode::Insensitive)),
+            100
+        );
+
+        let optimal = ScoringConfig {
+            algorithm: Algorithm::Optimal,
+            case: CaseMode::Insensitive,
+            ..Default::default()
+        };
+        assert_eq!(
+            match_with("ab", "xAB", &optimal).unwrap().positions,
+            vec![1, 2]
+        );
+
+        let results = match_items_with("readme", &["src", "README.md"], &config(CaseMode::Smart));
+        assert_eq!(results[0].0, "README.md");
+    }
+
+    #[test]
+    fn test_unicode_case_folding() {
+        let insensitive = ScoringConfig {
+            case: CaseMode::Insensitive,
+            ..Default::default()
+        };
+        assert_eq!(score_with("straße", "STRAẞE", &insensitive), 100);
+        assert_eq!(score_with("ΣΟΦΟΣ", "σοφος", &insensitive), 100);
+        assert_eq!(score_with("привет", "ПРИВЕТ", &insensitive), 100);
+        assert!(score_with("ſ", "S", &insensitive) > 0);
+    }
+
+    #[test]
+    fn test_unicode_scores_like_ascii() {
+        // Same shape of match, different scripts: the score must only depend on
+        // char positions, never on how many bytes each char takes.
+        let corpus = [
+            ("cafe", "cafe!", "café", "café!"),
+            ("caf", "xcafe", "caf", "xcafé"),
+            ("fe", "cafe", "fé", "café"),
+            ("ab", "a_ab", "пр", "п_пр"),
+            ("ab", "xxab", "東京", "大阪東京"),
+            ("ab", "xaxb", "🦀🐍", "x🦀x🐍"),
+        ];
+        for (ascii_query, ascii_candi, query, candi) in corpus {
+            assert_eq!(
+                score(query, candi),
+                score(ascii_query, ascii_candi),
+                "{query} / {candi}"
+            );
+            let optimal = ScoringConfig {
+                algorithm: Algorithm::Optimal,
+                ..Default::default()
+            };
+            assert_eq!(
+                score_with(query, candi, &optimal),
+                score_with(ascii_query, ascii_candi, &optimal)
+            );
+            assert_eq!(
+                score_with_positions(query, candi).unwrap().positions,
+                score_with_positions(ascii_query, ascii_candi)
+                    .unwrap()
+                    .positions
+            );
+        }
+    }
+
+    #[test]
+    fn test_legacy_weights_keep_scores() {
+        let legacy = ScoringConfig {
+            position_weight: 10,
+            position_decay: PositionDecay::Linear(1),
+            exact_bonus: 15,
+            folded_exact_bonus: 0,
+            prefix_bonus: 0,
+            word_prefix_bonus: 0,
+            substring_bonus: 0,
+            char_score: 15,
+            start_bonus: 0,
+            boundary_bonus: 0,
+            camel_bonus: 0,
+            ..Default::default()
+        };
+        let expected = [
+            ("xb", "xbps-install", 66),
+            ("xb", "xargs-b", 46),
+            ("fefe", "feature", 0),
+            ("fefe", "fefe", 100),
+            ("gc", "git commit", 53),
+            ("ab", "a_xab", 53),
+            ("aab", "a_aab", 53),
+            ("cfg", "src/cfg.rs", 40),
+            ("cfg", "config.toml", 48),
+            ("f", "f", 100),
+            ("abc", "abcabc", 66),
+            ("ft", "feature", 56),
+        ];
+        for (query, candi, want) in expected {
+            assert_eq!(score_with(query, candi, &legacy), want, "{query} / {candi}");
+        }
+    }
+
+    #[test]
+    fn test_word_boundary_bonuses() {
+        let optimal = ScoringConfig {
+            algorithm: Algorithm::Optimal,
+            ..Default::default()
+        };
+        assert!(score("gc", "git commit") > score("gc", "magic cat"));
+        assert!(score("gc", "agxcx") < score("gc", "git commit"));
+        // A contiguous substring earns its tier bonus, so it outranks an
+        // acronym placement even in the middle of a word.
+        assert!(score("gc", "agcx") > score("gc", "git commit"));
+        let insensitive = ScoringConfig {
+            case: CaseMode::Insensitive,
+            ..Default::default()
+        };
+        assert!(
+            score_with("fb", "fooBar", &insensitive) > score_with("fb", "foobar", &insensitive)
+        );
+        assert!(score_with("cfg", "src/cfg.rs", &optimal) > score_with("cfg", "srcfxgx", &optimal));
+        assert_eq!(
+            match_with("bc", "abxc b_c", &optimal).unwrap().positions,
+            vec![5, 7]
+        );
+        assert_eq!(
+            match_with("ms", "my-sql xmssql", &optimal)
+                .unwrap()
+                .positions,
+            vec![0, 3]
+        );
+        assert_eq!(
+            match_with("bc", "abc b_c", &optimal).unwrap().positions,
+            vec![1, 2]
+        );
+        assert_eq!(
+            match_with("ms", "my-sql mssql", &optimal)
+                .unwrap()
+                .positions,
+            vec![7, 8]
+        );
+    }
+
+    #[test]
+    fn test_match_tiers() {
+        let insensitive = ScoringConfig {
+            case: CaseMode::Insensitive,
+            ..Default::default()
+        };
+        let tiers = [
+            ("cat", Tier::Exact),
+            ("Cat", Tier::FoldedExact),
+            ("catalog", Tier::Prefix),
+            ("con_cat", Tier::WordPrefix),
+            ("concat", Tier::Substring),
+            ("c_a_t", Tier::Scattered),
+        ];
+        for (candi, tier) in tiers {
+            assert_eq!(explain_with("cat", candi, &insensitive).unwrap().tier, tier);
+        }
+        let mut shuffled: Vec<_> = tiers.iter().map(|(candi, _)| *candi).collect();
+        shuffled.reverse();
+        for algorithm in [Algorithm::Greedy, Algorithm::Optimal] {
+            let config = ScoringConfig {
+                algorithm,
+                ..insensitive
+            };
+            let ranked: Vec<_> = match_items_with("cat", &shuffled, &config)
+                .into_iter()
+                .map(|(item, _)| item)
+                .collect();
+            assert_eq!(
+                ranked,
+                ["cat", "Cat", "catalog", "con_cat", "concat", "c_a_t"]
+            );
+        }
+
+        // The contiguous run wins over the scattered placement greedy finds first.
+        assert_eq!(
+            score_with_positions("cat", "concat").unwrap().positions,
+            vec![3, 4, 5]
+        );
+        assert!(score("cat", "catalog") < 100);
+    }
+
+    #[test]
+    fn test_custom_weights() {
+        let config = ScoringConfig {
+            gap_penalty: 30,
+            algorithm: Algorithm::Optimal,
+            ..Default::default()
+        };
+        assert_eq!(
+            match_with("ab", "a_xab", &config).unwrap().positions,
+            vec![3, 4]
+        );
+
+        let prefix = ScoringConfig {
+            prefix_bonus: 150,
+            ..Default::default()
+        };
+        assert!(
+            match_with("ca", "cat", &prefix).unwrap().raw
+                > score_with_positions("ca", "cat").unwrap().raw
+        );
+        assert_eq!(score_with("ca", "ca", &prefix), 100);
+
+        let scaled = ScoringConfig {
+            max_score: 1000,
+            ..Default::default()
+        };
+        assert_eq!(score_with("fefe", "fefe", &scaled), 1000);
+        assert!(score_with("xb", "xbps-install", &scaled) > 600);
+    }
+
+    #[test]
+    fn test_position_weight_beyond_column_ten() {
+        let query = "index";
+        let candidates = [
+            "src/components/button/index.tsx",
+            "src/components/dialog/dialog-index.tsx",
+            "src/components/navigation/sidebar/index.tsx",
+            "src/components/navigation/sidebar/items/index.tsx",
+        ];
+        let optimal = ScoringConfig {
+            algorithm: Algorithm::Optimal,
+            ..Default::default()
+        };
+        let mut shuffled = candidates;
+        shuffled.reverse();
+        let results = match_items_with(query, &shuffled, &optimal);
+        let ranked: Vec<_> = results.iter().map(|(item, _)| *item).collect();
+        assert_eq!(ranked, candidates);
+        assert!(results.windows(2).all(|w| w[0].1 > w[1].1), "{results:?}");
+
+        assert!(score(query, candidates[0]) > score(query, candidates[3]));
+        assert!(score("zz", "src/components/zz") > 0);
+        assert!(
+            score_with("btn", "src/components/button.tsx", &optimal)
+                > score_with("btn", "src/components/forms/button.tsx", &optimal)
+        );
+        assert!(
+            score_with("main", "crates/core/src/bin/main.rs", &optimal)
+                > score_with(
+                    "main",
+                    "crates/core/src/bin/nested/deeper/main.rs",
+                    &optimal
+                )
+        );
+    }
+
+    #[test]
+    fn test_raw_score_breaks_ties() {
+        let a = score_with_positions("idx", "lib/components/index.tsx").unwrap();
+        let b = score_with_positions("idx", "src/component/index.tsx").unwrap();
+        assert_eq!(a.score, b.score);
+        assert!(b.raw > a.raw);
+
+        let items = ["lib/components/index.tsx", "src/component/index.tsx"];
+        assert_eq!(match_items("idx", &items)[0].0, items[1]);
+        let options = MatchOptions::default();
+        assert_eq!(filter_items("idx", &items, &options)[0].0, items[1]);
+        assert_eq!(par_match_items("idx", &items)[0].0, items[1]);
+
+        let exact = score_with_positions("xb", "xb").unwrap();
+        assert_eq!((exact.score, exact.raw), (100, 262 + 400));
+    }
+
+    #[test]
+    fn test_match_by_key() {
+        struct Command {
+            name: &'static str,
+            id: usize,
+        }
+        let commands = [
+            Command {
+                name: "git push",
+                id: 1,
+            },
+            Command {
+                name: "grep",
+                id: 2,
+            },
+            Command {
+                name: "git commit",
+                id: 3,
+            },
+        ];
+        let results = match_by_key("gc", &commands, |cmd| cmd.name);
+        let names: Vec<_> = results.iter().map(|(cmd, _)| cmd.name).collect();
+        let expected = match_items("gc", &["git push", "grep", "git commit"]);
+        assert_eq!(
+            names,
+            expected.iter().map(|(name, _)| *name).collect::<Vec<_>>()
+        );
+        assert_eq!(results[0].0.id, 3);
+        assert!(std::ptr::eq(results[0].0, &commands[2]));
+
+        let owned: Vec<String> = commands.iter().map(|cmd| cmd.name.to_string()).collect();
+        let results = match_as_ref("gc", &owned);
+        assert_eq!(results[0], (&owned[2], expected[0].1));
+    }
+
+    #[test]
+    fn test_positions_multibyte() {
+        let m = score_with_positions("éb", "aébc").unwrap();
+        assert_eq!(m.positions, vec![1, 2]);
+        assert_eq!(m.byte_positions, vec![1, 3]);
+    }
 }
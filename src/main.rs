@@ -0,0 +1,445 @@
+//! The `matchr` command: ranks the lines of stdin against a query, like `fzf --filter`,
+//! or lets the user pick among them interactively when built with the `picker` feature.
+
+use std::borrow::Cow;
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+
+use matchr::{
+    filter_by_key, filter_extended, CaseMode, Delimiter, ExtendedQuery, Fields, MatchOptions,
+};
+
+const USAGE: &str = "\
+Usage: matchr -f QUERY [OPTIONS] < candidates
+       matchr -i [-f QUERY] [OPTIONS] < candidates
+
+Prints the lines of stdin that match QUERY, best match first.
+Exits with 0 if anything matched, 1 if nothing did, 2 on errors.
+
+Like fzf, QUERY uses the extended search syntax: space-separated terms must
+all match, 'wild matches a substring, ^music and .mp3$ anchor it, !fire
+excludes lines, and a | b matches either; the -i picker matches plainly.
+Matching ignores case unless QUERY contains an uppercase letter.
+
+With -i, opens a picker on the terminal and prints the chosen lines.
+Exits with 0 if a line was chosen, 1 if none matched, 130 when cancelled.
+
+Options:
+  -f, --filter QUERY   Query to match, or the initial query with -i
+                       (alias: -q, --query)
+  -i, --interactive    Pick lines interactively (needs the `picker` feature)
+  -m, --multi          With -i, mark several lines with Tab
+      --nth FIELDS     Only match these fields, e.g. 2.. or 1,-1
+      --with-nth FIELDS
+                       Only print (or, with -i, list) these fields; --nth
+                       counts the fields of the result
+  -d, --delimiter STR  Split fields on STR instead of whitespace
+  +x, --no-extended    Match QUERY as one fuzzy pattern, without extended syntax
+  +i, --no-ignore-case Match case exactly
+      --ignore-case    Ignore case, even if QUERY has uppercase letters
+      --smart-case     Ignore case unless QUERY has uppercase letters (default)
+      --print-score    Prefix every match with its score and a tab
+      --limit N        Print at most N matches
+      --min-score N    Only print matches scoring at least N
+      --read0          Read NUL-separated candidates instead of lines
+      --print0         Separate printed matches with NUL instead of newline
+  -h, --help           Print this help
+";
+
+/// Parsed command-line arguments.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Args {
+    query: Option<String>,
+    options: MatchOptions,
+    interactive: bool,
+    multi: bool,
+    no_extended: bool,
+    nth: Option<Fields>,
+    with_nth: Option<Fields>,
+    print_score: bool,
+    read0: bool,
+    print0: bool,
+    help: bool,
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("matchr: {err}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    if args.help {
+        print!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    let mut input = Vec::new();
+    let result = io::stdin().lock().read_to_end(&mut input).and_then(|_| {
+        let mut out = io::BufWriter::new(io::stdout().lock());
+        let found = run(&args, &input, &mut out)?;
+        out.flush()?;
+        Ok(found)
+    });
+    if let Err(err) = &result {
+        if err.kind() != io::ErrorKind::BrokenPipe {
+            eprintln!("matchr: {err}");
+        }
+    }
+    ExitCode::from(exit_status(&result))
+}
+
+/// Filters or picks from `input`, writing the result to `out`.
+///
+/// Returns whether anything was printed, or `None` if the user cancelled the picker.
+fn run(args: &Args, input: &[u8], out: &mut impl Write) -> io::Result<Option<bool>> {
+    if args.interactive {
+        pick(args, input, out)
+    } else {
+        filter(args, input, out).map(Some)
+    }
+}
+
+/// Maps the outcome of [`run`] to the exit status documented in [`USAGE`].
+fn exit_status(result: &io::Result<Option<bool>>) -> u8 {
+    match result {
+        Ok(Some(true)) => 0,
+        Ok(Some(false)) => 1,
+        Ok(None) => 130,
+        // The reader (e.g. `head`) has seen enough.
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => 0,
+        Err(_) => 2,
+    }
+}
+
+/// Parses the arguments following the program name.
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    parsed.options.config.case = CaseMode::Smart;
+    let (mut nth, mut with_nth, mut delimiter) = (None, None, Delimiter::Whitespace);
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => {
+                (flag.to_string(), Some(value.to_string()))
+            }
+            _ => (arg, None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{flag} needs a value"))
+        };
+        match flag.as_str() {
+            "-f" | "--filter" | "-q" | "--query" => parsed.query = Some(value()?),
+            "--limit" => parsed.options.limit = Some(number(&flag, &value()?)?),
+            "--min-score" => parsed.options.min_score = number(&flag, &value()?)?,
+            "-i" | "--interactive" => parsed.interactive = true,
+            "-m" | "--multi" => parsed.multi = true,
+            "--nth" => nth = Some(value()?),
+            "--with-nth" => with_nth = Some(value()?),
+            "-d" | "--delimiter" => delimiter = Delimiter::Str(value()?),
+            "+x" | "--no-extended" => parsed.no_extended = true,
+            "+i" | "--no-ignore-case" => parsed.options.config.case = CaseMode::Sensitive,
+            "--ignore-case" => parsed.options.config.case = CaseMode::Insensitive,
+            "--smart-case" => parsed.options.config.case = CaseMode::Smart,
+            "--print-score" => parsed.print_score = true,
+            "--read0" => parsed.read0 = true,
+            "--print0" => parsed.print0 = true,
+            "-h" | "--help" => parsed.help = true,
+            _ => return Err(format!("unknown option `{flag}`")),
+        }
+    }
+    let fields = |spec: Option<String>, flag: &str| {
+        spec.map(|spec| Fields::parse(&spec, delimiter.clone()))
+            .transpose()
+            .map_err(|err| format!("{flag}: {err}"))
+    };
+    parsed.nth = fields(nth, "--nth")?;
+    parsed.with_nth = fields(with_nth, "--with-nth")?;
+    if parsed.query.is_none() && !parsed.interactive && !parsed.help {
+        return Err("missing query, pass it with -f QUERY".to_string());
+    }
+    if let Some(query) = parsed.query.as_deref().filter(|_| parsed.extended()) {
+        ExtendedQuery::parse(query).map_err(|err| format!("invalid query: {err}"))?;
+    }
+    Ok(parsed)
+}
+
+fn number(flag: &str, value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{flag} expects a number, got `{value}`"))
+}
+
+/// Splits `input` into items on `separator`, ignoring a trailing separator.
+fn split_items(input: &[u8], separator: u8) -> Vec<&[u8]> {
+    let input = input.strip_suffix(&[separator]).unwrap_or(input);
+    if input.is_empty() {
+        return Vec::new();
+    }
+    input.split(|&b| b == separator).collect()
+}
+
+impl Args {
+    /// Returns whether the query is parsed in the extended search syntax.
+    fn extended(&self) -> bool {
+        !self.no_extended && !self.interactive
+    }
+}
+
+/// Returns the `fields` of `text`, or all of it.
+fn select<'a>(fields: &Option<Fields>, text: &'a str) -> Cow<'a, str> {
+    match fields {
+        Some(fields) => fields.select(text),
+        None => Cow::Borrowed(text),
+    }
+}
+
+/// Writes the items of `input` matching the query to `out`, best first.
+///
+/// Like `fzf --filter`, an empty (or, in extended mode, blank) query prints
+/// every item in input order.
+///
+/// Returns whether any item was printed.
+fn filter(args: &Args, input: &[u8], out: &mut impl Write) -> io::Result<bool> {
+    let items = split_items(input, if args.read0 { b'\0' } else { b'\n' });
+    let lines: Vec<_> = items
+        .iter()
+        .map(|item| String::from_utf8_lossy(item))
+        .collect();
+    let views: Vec<_> = lines
+        .iter()
+        .map(|line| select(&args.with_nth, line))
+        .collect();
+    let indices: Vec<usize> = (0..items.len()).collect();
+    let query = args.query.as_deref().unwrap_or_default();
+    let blank = query.is_empty() || args.extended() && query.trim().is_empty();
+    let key = |&i: &usize| select(&args.nth, &views[i]);
+    let results: Vec<(&usize, usize)> = if blank {
+        indices
+            .iter()
+            .map(|index| (index, 0))
+            .filter(|_| args.options.min_score == 0)
+            .take(args.options.limit.unwrap_or(usize::MAX))
+            .collect()
+    } else if args.extended() {
+        filter_extended(query, &indices, key, &args.options)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?
+    } else {
+        filter_by_key(query, &indices, key, &args.options)
+    };
+
+    let separator = if args.print0 { b'\0' } else { b'\n' };
+    for &(&i, score) in &results {
+        if args.print_score {
+            write!(out, "{score}\t")?;
+        }
+        // Print the bytes as read unless only some fields are wanted.
+        match args.with_nth {
+            Some(_) => out.write_all(views[i].as_bytes())?,
+            None => out.write_all(items[i])?,
+        }
+        out.write_all(&[separator])?;
+    }
+    Ok(!results.is_empty())
+}
+
+/// Lets the user pick among the items of `input` and writes the chosen ones to `out`.
+///
+/// Returns whether anything was chosen, or `None` if the user cancelled.
+#[cfg(feature = "picker")]
+fn pick(args: &Args, input: &[u8], out: &mut impl Write) -> io::Result<Option<bool>> {
+    let separator = if args.read0 { b'\0' } else { b'\n' };
+    let items = split_items(input, separator);
+    let lines: Vec<_> = items
+        .iter()
+        .map(|item| String::from_utf8_lossy(item))
+        .collect();
+    let views: Vec<_> = lines
+        .iter()
+        .map(|line| select(&args.with_nth, line))
+        .collect();
+    let views: Vec<&str> = views.iter().map(AsRef::as_ref).collect();
+    let options = matchr::PickerOptions {
+        options: args.options,
+        query: args.query.clone().unwrap_or_default(),
+        multi: args.multi,
+        nth: args.nth.clone(),
+        ..Default::default()
+    };
+    let Some(chosen) = matchr::Picker::with_options(&views, options).run_indices()? else {
+        return Ok(None);
+    };
+    // The chosen lines are printed whole, whatever --with-nth shows.
+    let separator = if args.print0 { b'\0' } else { b'\n' };
+    for &i in &chosen {
+        out.write_all(items[i])?;
+        out.write_all(&[separator])?;
+    }
+    Ok(Some(!chosen.is_empty()))
+}
+
+#[cfg(not(feature = "picker"))]
+fn pick(_: &Args, _: &[u8], _: &mut impl Write) -> io::Result<Option<bool>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "-i needs matchr built with the `picker` feature",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Args, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn run(flags: &[&str], input: &str) -> (bool, String) {
+        let mut out = Vec::new();
+        let found = filter(&args(flags).unwrap(), input.as_bytes(), &mut out).unwrap();
+        (found, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed = args(&["-f", "xb", "--limit=2", "--min-score", "40", "--print0"]).unwrap();
+        assert_eq!(parsed.query.as_deref(), Some("xb"));
+        assert_eq!(parsed.options.limit, Some(2));
+        assert_eq!(parsed.options.min_score, 40);
+        assert!(parsed.print0 && !parsed.read0 && !parsed.print_score);
+        assert_eq!(
+            args(&["--query=a=b"]).unwrap().query.as_deref(),
+            Some("a=b")
+        );
+        assert!(args(&["--help"]).unwrap().help);
+        let parsed = args(&["-i", "-m"]).unwrap();
+        assert!(parsed.interactive && parsed.multi && parsed.query.is_none());
+
+        assert!(args(&[]).is_err());
+        assert!(args(&["-f"]).is_err());
+        assert!(args(&["-f", "x", "--limit", "ten"]).is_err());
+        assert!(args(&["-f", "x", "--frobnicate"]).is_err());
+    }
+
+    #[test]
+    fn test_filter_ranks_matches() {
+        let input = "grep\nxbps-remove\nxargs-b\nxbps-install\n";
+        let (found, out) = run(&["-f", "xb"], input);
+        assert!(found);
+        let ranked: Vec<_> = matchr::filter_items(
+            "xb",
+            &["grep", "xbps-remove", "xargs-b", "xbps-install"],
+            &MatchOptions::default(),
+        )
+        .into_iter()
+        .map(|(item, _)| format!("{item}\n"))
+        .collect();
+        assert_eq!(out, ranked.concat());
+
+        let (_, out) = run(&["-f", "xb", "--limit", "1", "--print-score"], input);
+        assert_eq!(
+            out,
+            format!("{}\txbps-remove\n", matchr::score("xb", "xbps-remove"))
+        );
+        assert_eq!(run(&["-f", "zz"], input), (false, String::new()));
+        assert_eq!(
+            run(&["-f", "xb", "--min-score", "101"], input),
+            (false, String::new())
+        );
+    }
+
+    #[test]
+    fn test_filter_like_fzf() {
+        let input = "Cargo.toml\nsrc/lib.rs\nsrc/main.rs\ntests/lib.rs\n";
+        assert_eq!(run(&["-f", "cargo"], input).1, "Cargo.toml\n");
+        assert_eq!(run(&["-f", "Cargo"], input).1, "Cargo.toml\n");
+        assert_eq!(run(&["-f", "cargo", "+i"], input), (false, String::new()));
+        let (_, out) = run(&["-f", "^src .rs$ !main"], input);
+        assert_eq!(out, "src/lib.rs\n");
+        let (_, out) = run(&["-f", "toml$ | ^tests"], input);
+        assert_eq!(out.lines().count(), 2);
+        assert_eq!(run(&["-f", "^src", "+x"], input), (false, String::new()));
+        assert_eq!(run(&["-f", " "], "b\na\n"), (true, "b\na\n".to_string()));
+
+        let parsed = args(&["-f", "X", "--ignore-case"]).unwrap();
+        assert_eq!(parsed.options.config.case, CaseMode::Insensitive);
+        assert!(args(&["-f", "foo |"]).is_err());
+        assert!(args(&["-f", "foo |", "+x"]).is_ok());
+    }
+
+    #[test]
+    fn test_filter_ties_like_filter_items() {
+        let items = ["lib/components/index.tsx", "src/component/index.tsx"];
+        let input = items.join("\n") + "\n";
+        let parsed = args(&["-f", "idx"]).unwrap();
+        let expected: String = matchr::filter_items("idx", &items, &parsed.options)
+            .into_iter()
+            .map(|(item, _)| format!("{item}\n"))
+            .collect();
+        assert_eq!(
+            expected,
+            "src/component/index.tsx\nlib/components/index.tsx\n"
+        );
+        assert_eq!(run(&["-f", "idx"], &input).1, expected);
+        assert_eq!(run(&["-f", "idx", "+x"], &input).1, expected);
+    }
+
+    #[test]
+    fn test_filter_nul_separated() {
+        let (found, out) = run(&["-f", "ab", "--read0", "--print0"], "a\nb\0xy\0ab");
+        assert!(found);
+        assert_eq!(out, "ab\0a\nb\0");
+        let (_, out) = run(&["-f", "ab", "--read0"], "a\nb\0");
+        assert_eq!(out, "a\nb\n");
+    }
+
+    #[test]
+    fn test_filter_fields() {
+        let input = "a1b2 fix typo\nd4e5 add ranking\nfeed adjust\n";
+        let (_, out) = run(&["-f", "ad", "--nth", "2.."], input);
+        assert_eq!(out, "d4e5 add ranking\nfeed adjust\n");
+        let (_, out) = run(&["-f", "fe", "--nth", "1"], input);
+        assert_eq!(out, "feed adjust\n");
+        let (_, out) = run(&["-f", "fx", "--with-nth", "2..", "--nth", "1"], input);
+        assert_eq!(out, "fix typo\n");
+        let (_, out) = run(&["-f", "", "-d", ":", "--with-nth=-1"], "a:b\nc\n");
+        assert_eq!(out, "b\nc\n");
+
+        assert!(args(&["-f", "x", "--nth", "0"]).is_err());
+        assert!(args(&["-f", "x", "--nth", "1", "-d", ""]).is_err());
+    }
+
+    #[test]
+    fn test_exit_status() {
+        let status = |flags: &[&str], input: &str| {
+            exit_status(&super::run(
+                &args(flags).unwrap(),
+                input.as_bytes(),
+                &mut Vec::new(),
+            ))
+        };
+        assert_eq!(status(&["-f", "Cargo"], "Cargo.toml\n"), 0);
+        assert_eq!(status(&["-f", "zzz"], "Cargo.toml\n"), 1);
+        assert_eq!(status(&["-f", "Cargo", "--limit", "0"], "Cargo.toml\n"), 1);
+        assert_eq!(status(&["-f", ""], ""), 1);
+
+        let broken = io::Error::from(io::ErrorKind::BrokenPipe);
+        assert_eq!(exit_status(&Err(broken)), 0);
+        assert_eq!(exit_status(&Err(io::Error::other("disk full"))), 2);
+        assert_eq!(exit_status(&Ok(None)), 130);
+    }
+
+    #[test]
+    fn test_empty_query_prints_everything() {
+        assert_eq!(run(&["-f", ""], "b\na\n"), (true, "b\na\n".to_string()));
+        assert_eq!(
+            run(&["-f", "", "--limit", "1"], "b\na\n"),
+            (true, "b\n".to_string())
+        );
+        assert_eq!(run(&["-f", "x"], ""), (false, String::new()));
+    }
+}
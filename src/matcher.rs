@@ -0,0 +1,1237 @@
+use std::fmt;
+
+use crate::config::{fold_if, Algorithm, PositionDecay, ScoringConfig};
+use crate::explain::{CharScore, Explanation};
+
+/// A successful match of a query against a candidate string.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Match {
+    /// The match score, between 0 and 100, as returned by [`score`](crate::score).
+    pub score: usize,
+    /// The unnormalized score behind `score`: the points of every matched
+    /// character plus the prefix and exact bonuses, before they are scaled and
+    /// rounded onto `0..=max_score`. Ranked results order items with equal
+    /// scores by it.
+    pub raw: usize,
+    /// Char index in the candidate of every matched query character, in query order.
+    ///
+    /// For a match with typos, only the candidate characters equal to a query
+    /// character are listed, in candidate order.
+    pub positions: Vec<usize>,
+    /// Byte offset in the candidate of every entry of `positions`.
+    pub byte_positions: Vec<usize>,
+    /// Number of typos the match needed; always 0 unless `config.max_typos` is set.
+    pub typos: usize,
+}
+
+/// A query compiled once and scored against many candidates.
+///
+/// The query is split into (case-folded) chars and a char-set bitmask up front,
+/// and the buffers used while scoring are kept between calls, so re-scoring a
+/// large list on every keystroke neither re-parses the query nor allocates per item.
+///
+/// # Examples
+///
+/// ```
+/// use matchr::Matcher;
+///
+/// let mut matcher = Matcher::new("xb");
+/// assert!(matcher.score("xbps-install") > matcher.score("xargs-b"));
+/// assert_eq!(matcher.score("grep"), 0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    query: String,
+    config: ScoringConfig,
+    fold: bool,
+    chars: Vec<char>,
+    mask: u64,
+    // failure[i]: length of the longest proper prefix of chars[..=i] that is
+    // also its suffix, for the linear substring search of `first_run`.
+    failure: Vec<usize>,
+    // One matcher per query term in `tokenize` mode.
+    tokens: Vec<Matcher>,
+    // One matcher per query segment for a `path` mode query containing separators.
+    segments: Vec<Matcher>,
+    // Scratch buffers reused across candidates.
+    candi: Vec<(usize, char, usize)>,
+    prev: Vec<Option<usize>>,
+    cur: Vec<Option<usize>>,
+    back: Vec<usize>,
+    edits: Vec<usize>,
+    spans: Vec<(usize, usize)>,
+    positions: Vec<usize>,
+    byte_positions: Vec<usize>,
+    raw: usize,
+    tier: Tier,
+    typos: usize,
+}
+
+impl Matcher {
+    /// Compiles `query` with the default [`ScoringConfig`].
+    pub fn new(query: &str) -> Self {
+        Self::with_config(query, ScoringConfig::default())
+    }
+
+    /// Compiles `query` with the given `config`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use matchr::{CaseMode, Matcher, ScoringConfig};
+    ///
+    /// let config = ScoringConfig {
+    ///     case: CaseMode::Smart,
+    ///     ..Default::default()
+    /// };
+    /// let mut matcher = Matcher::with_config("cargo", config);
+    /// assert!(matcher.score("Cargo.toml") > 0);
+    /// ```
+    pub fn with_config(query: &str, config: ScoringConfig) -> Self {
+        let fold = config.case.folds(query);
+        let chars: Vec<char> = query.chars().map(|c| fold_if(c, fold)).collect();
+        let mask = chars.iter().fold(0, |mask, &c| mask | char_bit(c));
+        let mut failure = vec![0; chars.len()];
+        let mut k = 0;
+        for i in 1..chars.len() {
+            while k > 0 && chars[i] != chars[k] {
+                k = failure[k - 1];
+            }
+            if chars[i] == chars[k] {
+                k += 1;
+            }
+            failure[i] = k;
+        }
+        let mut tokens = Vec::new();
+        if config.tokenize && query.contains(char::is_whitespace) {
+            let config = ScoringConfig {
+                tokenize: false,
+                ..config
+            };
+            tokens.extend(
+                query
+                    .split_whitespace()
+                    .map(|term| Matcher::with_config(term, config)),
+            );
+        }
+        let mut segments = Vec::new();
+        if config.path && tokens.is_empty() && query.contains(is_path_separator) {
+            // Each segment is matched against a single path segment.
+            let plain = ScoringConfig {
+                path: false,
+                ..config
+            };
+            segments.extend(
+                query
+                    .split(is_path_separator)
+                    .filter(|segment| !segment.is_empty())
+                    .map(|segment| Matcher::with_config(segment, plain)),
+            );
+        }
+        Matcher {
+            query: query.to_string(),
+            config,
+            fold,
+            chars,
+            mask,
+            failure,
+            tokens,
+            segments,
+            candi: Vec::new(),
+            prev: Vec::new(),
+            cur: Vec::new(),
+            back: Vec::new(),
+            edits: Vec::new(),
+            spans: Vec::new(),
+            positions: Vec::new(),
+            byte_positions: Vec::new(),
+            raw: 0,
+            tier: Tier::Scattered,
+            typos: 0,
+        }
+    }
+
+    /// Returns the query this matcher was compiled from.
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Returns the scoring options this matcher was compiled with.
+    pub fn config(&self) -> &ScoringConfig {
+        &self.config
+    }
+
+    /// Scores how well the query matches `candi`, like [`score_with`](crate::score_with).
+    ///
+    /// # Returns
+    ///
+    /// A usize score between 0 and `config.max_score`, higher means better match.
+    pub fn score(&mut self, candi: &str) -> usize {
+        self.run(candi).unwrap_or(0)
+    }
+
+    /// Scores `candi` like [`Matcher::score`], but returns `None` instead of 0
+    /// when the query does not match at all.
+    pub(crate) fn try_score(&mut self, candi: &str) -> Option<usize> {
+        self.run(candi)
+    }
+
+    /// Returns the char positions matched by the last scored candidate.
+    pub(crate) fn positions(&self) -> &[usize] {
+        &self.positions
+    }
+
+    /// Returns the unnormalized score of the last scored candidate.
+    pub(crate) fn raw(&self) -> usize {
+        self.raw
+    }
+
+    /// Returns the number of typos the last scored candidate needed.
+    pub(crate) fn typos(&self) -> usize {
+        self.typos
+    }
+
+    /// Returns the number of `tokenize` mode query terms, 0 for a plain query.
+    pub(crate) fn token_count(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Returns the number of `path` mode query segments, including those of
+    /// every `tokenize` mode term, 0 for a plain query.
+    pub(crate) fn segment_count(&self) -> usize {
+        let nested: usize = self.tokens.iter().map(Matcher::segment_count).sum();
+        self.segments.len() + nested
+    }
+
+    /// Scores the query against `candi` and reports which candidate characters
+    /// were matched, like [`match_with`](crate::match_with).
+    ///
+    /// # Returns
+    ///
+    /// `Some(Match)` if the query is a non-empty subsequence of `candi`, otherwise `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut matcher = matchr::Matcher::new("ft");
+    /// assert_eq!(matcher.find("feature").unwrap().positions, vec![0, 3]);
+    /// ```
+    pub fn find(&mut self, candi: &str) -> Option<Match> {
+        let score = self.run(candi)?;
+        Some(Match {
+            score,
+            raw: self.raw,
+            positions: self.positions.clone(),
+            byte_positions: self.byte_positions.clone(),
+            typos: self.typos,
+        })
+    }
+
+    /// Scores the query against `candi` like [`Matcher::find`] and breaks the
+    /// score down into the contribution of every matched character.
+    ///
+    /// In `tokenize` mode, and for a `path` mode query containing separators,
+    /// the breakdown of every query term or segment is in [`Explanation::terms`] instead.
+    ///
+    /// # Returns
+    ///
+    /// `Some(Explanation)` if the query matches `candi`, otherwise `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut matcher = matchr::Matcher::new("gc");
+    /// let explanation = matcher.explain("git commit").unwrap();
+    /// assert_eq!(explanation.chars[1].position, 4);
+    /// assert_eq!(explanation.score, matcher.score("git commit"));
+    /// ```
+    pub fn explain(&mut self, candi: &str) -> Option<Explanation> {
+        let score = self.run(candi)?;
+        let mut explanation = Explanation {
+            query: self.query.clone(),
+            candidate: candi.to_string(),
+            score,
+            typos: self.typos,
+            ..Default::default()
+        };
+        if !self.tokens.is_empty() {
+            explanation.terms = self
+                .tokens
+                .iter_mut()
+                .map(|token| token.explain(candi))
+                .collect::<Option<_>>()?;
+            return Some(explanation);
+        }
+        if !self.segments.is_empty() {
+            explanation.terms = self
+                .segments
+                .iter_mut()
+                .zip(&self.spans)
+                .map(|(segment, &(start, end))| segment.explain(&candi[start..end]))
+                .collect::<Option<_>>()?;
+            return Some(explanation);
+        }
+
+        self.load(candi);
+        let mut raw = 0;
+        let mut last_pos = None;
+        for (&pos, &byte) in self.positions.iter().zip(&self.byte_positions) {
+            let char_score = CharScore {
+                ch: candi[byte..].chars().next()?,
+                byte_position: byte,
+                ..step(&self.config, raw, pos, last_pos, self.candi[pos].2)
+            };
+            raw = char_score.running;
+            last_pos = Some(pos);
+            explanation.chars.push(char_score);
+        }
+        explanation.raw = raw;
+        explanation.tier = self.tier;
+        explanation.bonus = self.tier_bonus(self.tier);
+        explanation.max_raw = self.max_raw();
+        explanation.normalized = self.scale(raw + explanation.bonus);
+        Some(explanation)
+    }
+
+    /// Scores `candi` with the configured algorithm, leaving the matched
+    /// positions in `self.positions` / `self.byte_positions`.
+    fn run(&mut self, candi: &str) -> Option<usize> {
+        if self.chars.is_empty() {
+            return None;
+        }
+        self.positions.clear();
+        self.byte_positions.clear();
+        self.raw = 0;
+        self.tier = Tier::Scattered;
+        self.typos = 0;
+        if !self.tokens.is_empty() {
+            return self.run_tokens(candi);
+        }
+        if !self.segments.is_empty() {
+            return self.run_segments(candi);
+        }
+
+        let scattered = match self.config.algorithm {
+            Algorithm::Greedy => self.greedy(candi),
+            Algorithm::Optimal => self.optimal(candi),
+        };
+        // A contiguous occurrence earns its tier bonus, which usually makes it
+        // beat the best scattered placement. Greedy settles for the first
+        // occurrence, found in one pass; Optimal compares them all.
+        let exact = scattered.map(|raw| {
+            self.load(candi);
+            let starts = match self.config.algorithm {
+                Algorithm::Greedy => self.first_run().map(|start| start..=start),
+                Algorithm::Optimal => Some(0..=self.candi.len() - self.chars.len()),
+            };
+            match starts.and_then(|starts| self.best_run(candi, starts)) {
+                Some((total, start, tier)) if total >= raw => {
+                    self.place_run(start);
+                    self.tier = tier;
+                    total
+                }
+                _ => raw,
+            }
+        });
+        if let Some(raw) = exact {
+            let score = self.normalize(raw);
+            return Some(self.above_typos(score));
+        }
+        if self.config.max_typos == 0 {
+            return None;
+        }
+        let raw = self.tolerant(candi)?;
+        let score = self.normalize(raw);
+        Some(typo_score(self.config.max_score, score, self.typos))
+    }
+
+    /// Maps the score of a match without typos onto the top three quarters of
+    /// the range when `config.max_typos` is set, leaving the bottom quarter to
+    /// matches with typos.
+    fn above_typos(&self, score: usize) -> usize {
+        if self.config.max_typos == 0 {
+            return score;
+        }
+        let max_score = self.config.max_score;
+        let ceiling = max_score / 4;
+        ceiling + score * (max_score - ceiling) / max_score.max(1)
+    }
+
+    /// Keeps the combined score of several terms or segments below
+    /// `max_score / 4` if any of them needed a typo, and above it otherwise.
+    fn combine_typos(&self, score: usize) -> usize {
+        match self.typos {
+            0 if self.config.max_typos > 0 => score.max(self.config.max_score / 4),
+            0 => score,
+            typos => typo_score(self.config.max_score, score, typos),
+        }
+    }
+
+    /// Scores every query term independently and averages the term scores,
+    /// minus `overlap_penalty` for every char covered by the spans of two terms.
+    ///
+    /// Returns `None` if any term does not match `candi`.
+    fn run_tokens(&mut self, candi: &str) -> Option<usize> {
+        let mut total = 0;
+        self.spans.clear();
+        for token in &mut self.tokens {
+            total += token.run(candi)?;
+            let first = *token.positions.first()?;
+            let last = *token.positions.last()?;
+            self.spans.push((first.min(last), first.max(last)));
+            self.positions.extend(&token.positions);
+            self.byte_positions.extend(&token.byte_positions);
+            self.raw += token.raw;
+            self.typos += token.typos;
+        }
+
+        let mut candi_chars = candi.chars();
+        let exact = self.chars.iter().all(|&qc| {
+            candi_chars
+                .next()
+                .is_some_and(|cc| fold_if(cc, self.fold) == qc)
+        });
+        if exact && candi_chars.next().is_none() {
+            return Some(self.config.max_score);
+        }
+
+        let mut overlap = 0;
+        for (i, &(start, end)) in self.spans.iter().enumerate() {
+            for &(other_start, other_end) in &self.spans[i + 1..] {
+                overlap += (end.min(other_end) + 1).saturating_sub(start.max(other_start));
+            }
+        }
+        let score = total / self.tokens.len();
+        let score = score.saturating_sub(overlap * self.config.overlap_penalty);
+        Some(self.combine_typos(score))
+    }
+
+    /// Matches every query segment inside its own path segment of `candi`, in
+    /// order, picking the assignment with the highest total score. The score is
+    /// the average segment score, below `max_score` unless `candi` equals the query.
+    ///
+    /// Returns `None` if the query segments cannot all be placed.
+    fn run_segments(&mut self, candi: &str) -> Option<usize> {
+        // Candidate segments as (byte start, byte end, char start).
+        let mut parts = Vec::new();
+        let (mut byte_start, mut char_start) = (0, 0);
+        for (char_pos, (pos, c)) in candi.char_indices().enumerate() {
+            if is_path_separator(c) {
+                parts.push((byte_start, pos, char_start));
+                (byte_start, char_start) = (pos + c.len_utf8(), char_pos + 1);
+            }
+        }
+        parts.push((byte_start, candi.len(), char_start));
+
+        // prev[j] / cur[j]: best total with the previous / current query segment
+        // in candidate segment j. back[i * s + j]: where query segment i - 1 sits.
+        let k = self.segments.len();
+        let s = parts.len();
+        self.prev.clear();
+        self.prev.resize(s, None);
+        self.cur.clear();
+        self.cur.resize(s, None);
+        self.back.clear();
+        self.back.resize(k * s, 0);
+        for (i, segment) in self.segments.iter_mut().enumerate() {
+            // Best (total, index) over prev[..j]; later segments win ties.
+            let mut best: Option<(usize, usize)> = None;
+            for (j, &(start, end, _)) in parts.iter().enumerate() {
+                self.cur[j] = None;
+                let reach = if i == 0 { Some((0, 0)) } else { best };
+                if let Some((total, from)) = reach {
+                    if let Some(score) = segment.run(&candi[start..end]) {
+                        self.cur[j] = Some(total + score);
+                        self.back[i * s + j] = from;
+                    }
+                }
+                if let Some(total) = self.prev[j].filter(|_| i > 0) {
+                    if best.is_none_or(|(b, _)| total >= b) {
+                        best = Some((total, j));
+                    }
+                }
+            }
+            std::mem::swap(&mut self.prev, &mut self.cur);
+        }
+
+        let mut end: Option<(usize, usize)> = None;
+        for (j, total) in self.prev.iter().enumerate() {
+            if let Some(total) = *total {
+                if end.is_none_or(|(b, _)| total >= b) {
+                    end = Some((total, j));
+                }
+            }
+        }
+        let (total, mut j) = end?;
+
+        self.spans.clear();
+        self.spans.resize(k, (0, 0));
+        for i in (0..k).rev() {
+            self.spans[i] = (parts[j].0, parts[j].1);
+            j = self.back[i * s + j];
+        }
+        for (segment, &(start, end)) in self.segments.iter_mut().zip(&self.spans) {
+            segment.run(&candi[start..end])?;
+            let char_start = candi[..start].chars().count();
+            self.positions
+                .extend(segment.positions.iter().map(|&pos| char_start + pos));
+            self.byte_positions
+                .extend(segment.byte_positions.iter().map(|&pos| start + pos));
+            self.raw += segment.raw;
+            self.typos += segment.typos;
+        }
+        // As in `scale`, only a candidate equal to the query reaches `max_score`.
+        let max_score = self.config.max_score;
+        let whole = candi.chars().map(|c| fold_if(c, self.fold));
+        let top = if whole.eq(self.chars.iter().copied()) {
+            max_score
+        } else {
+            max_score.saturating_sub(1)
+        };
+        Some(self.combine_typos((total / k).min(top)))
+    }
+
+    /// Walks `candi` consuming the first occurrence of each query character.
+    ///
+    /// Returns the raw running score, or `None` if the query is not a subsequence of `candi`.
+    fn greedy(&mut self, candi: &str) -> Option<usize> {
+        let mut score = 0usize;
+        let mut candi_chars = candi.char_indices().enumerate();
+        let mut last_pos = None;
+        let mut prev_char = None;
+        let basename = basename_start(&self.config, candi);
+
+        for &qc in &self.chars {
+            let (char_pos, pos, bonus) = loop {
+                let (char_pos, (pos, cc)) = candi_chars.next()?;
+                let prev = prev_char.replace(cc);
+                if fold_if(cc, self.fold) == qc {
+                    break (
+                        char_pos,
+                        pos,
+                        char_bonus(&self.config, prev, cc, pos >= basename),
+                    );
+                }
+            };
+            score = step_score(&self.config, score, char_pos, last_pos, bonus);
+            last_pos = Some(char_pos);
+            self.positions.push(char_pos);
+            self.byte_positions.push(pos);
+        }
+
+        Some(score)
+    }
+
+    /// Finds the placement of the query in `candi` that maximizes the running score.
+    ///
+    /// Every step of the running score is monotone in the previous value, so it is
+    /// enough to keep the best score for each (query char, candidate char) pair.
+    ///
+    /// Returns the raw running score, or `None` if the query is not a subsequence of `candi`.
+    fn optimal(&mut self, candi: &str) -> Option<usize> {
+        if self.mask & !self.load(candi) != 0 {
+            return None;
+        }
+        let config = &self.config;
+
+        let n = self.chars.len();
+        let m = self.candi.len();
+
+        // prev[j] / cur[j]: best running score with the previous / current query
+        // char matched at candidate char j. back[i * m + j]: where query char i - 1
+        // sits in that best placement.
+        self.prev.clear();
+        self.prev.resize(m, None);
+        self.cur.clear();
+        self.cur.resize(m, None);
+        self.back.clear();
+        self.back.resize(n * m, 0);
+
+        for (i, &qc) in self.chars.iter().enumerate() {
+            // Best (score, index) over prev[..j], ranked by `score + gap_penalty * index`
+            // so that the gap to any later j is already accounted for.
+            let mut best: Option<(usize, usize)> = None;
+            for (j, &(_, cc, bonus)) in self.candi.iter().enumerate() {
+                self.cur[j] = None;
+                if cc == qc {
+                    if i == 0 {
+                        self.cur[j] = Some(step_score(config, 0, j, None, bonus));
+                    } else if let Some((s, k)) = best {
+                        let mut step = (step_score(config, s, j, Some(k), bonus), k);
+                        if let Some(adj) = self.prev[j - 1] {
+                            let joined = step_score(config, adj, j, Some(j - 1), bonus);
+                            if joined > step.0 {
+                                step = (joined, j - 1);
+                            }
+                        }
+                        self.cur[j] = Some(step.0);
+                        self.back[i * m + j] = step.1;
+                    }
+                }
+                if let Some(s) = self.prev[j] {
+                    let key = |(s, k): (usize, usize)| s + config.gap_penalty * k;
+                    if best.is_none_or(|b| key((s, j)) > key(b)) {
+                        best = Some((s, j));
+                    }
+                }
+            }
+            std::mem::swap(&mut self.prev, &mut self.cur);
+        }
+
+        let mut end: Option<(usize, usize)> = None;
+        for (j, s) in self.prev.iter().enumerate() {
+            if let Some(s) = *s {
+                if end.is_none_or(|(b, _)| s > b) {
+                    end = Some((s, j));
+                }
+            }
+        }
+        let (score, mut j) = end?;
+
+        self.positions.resize(n, 0);
+        for i in (0..n).rev() {
+            self.positions[i] = j;
+            j = self.back[i * m + j];
+        }
+        self.byte_positions
+            .extend(self.positions.iter().map(|&j| self.candi[j].0));
+
+        Some(score)
+    }
+
+    /// Places the query in `candi` allowing up to `config.max_typos` edits: a query
+    /// character substituted in or missing from the candidate, or two adjacent
+    /// query characters swapped. Typos are limited to fewer than half of the
+    /// query characters.
+    ///
+    /// Returns the raw running score of the candidate characters equal to a query
+    /// character, or `None` if the query needs too many edits.
+    fn tolerant(&mut self, candi: &str) -> Option<usize> {
+        self.positions.clear();
+        self.byte_positions.clear();
+        let n = self.chars.len();
+        let allowed = self.config.max_typos.min((n - 1) / 2);
+        let mask = self.load(candi);
+        // Every query char absent from the candidate costs at least one edit.
+        let absent = self.chars.iter().filter(|&&qc| mask & char_bit(qc) == 0);
+        if allowed == 0 || absent.count() > allowed {
+            return None;
+        }
+
+        let q = &self.chars;
+        let c = &self.candi;
+        let m = c.len();
+        let w = m + 1;
+        let swapped =
+            |i: usize, j: usize| i + 1 < n && j + 1 < m && q[i] == c[j + 1].1 && q[i + 1] == c[j].1;
+
+        // edits[i * w + j]: fewest edits placing q[i..] inside candi[j..].
+        // Skipping a candidate char is free, as in any subsequence match.
+        let edits = &mut self.edits;
+        edits.clear();
+        edits.resize((n + 1) * w, 0);
+        for i in (0..n).rev() {
+            edits[i * w + m] = n - i;
+            for j in (0..m).rev() {
+                let diagonal = edits[(i + 1) * w + j + 1] + usize::from(q[i] != c[j].1);
+                let mut best = diagonal
+                    .min(edits[i * w + j + 1])
+                    .min(edits[(i + 1) * w + j] + 1);
+                if swapped(i, j) {
+                    best = best.min(edits[(i + 2) * w + j + 2] + 1);
+                }
+                edits[i * w + j] = best;
+            }
+        }
+        self.typos = edits[0];
+        if self.typos > allowed {
+            return None;
+        }
+
+        // Walk forward, matching characters as early as the edit count allows.
+        let (mut i, mut j) = (0, 0);
+        while i < n {
+            let here = edits[i * w + j];
+            if j < m && q[i] == c[j].1 && here == edits[(i + 1) * w + j + 1] {
+                self.positions.push(j);
+                (i, j) = (i + 1, j + 1);
+            } else if swapped(i, j) && here == edits[(i + 2) * w + j + 2] + 1 {
+                self.positions.extend([j, j + 1]);
+                (i, j) = (i + 2, j + 2);
+            } else if j < m && here == edits[i * w + j + 1] {
+                j += 1;
+            } else if j < m && here == edits[(i + 1) * w + j + 1] + 1 {
+                (i, j) = (i + 1, j + 1);
+            } else {
+                i += 1;
+            }
+        }
+
+        let mut score = 0;
+        let mut last_pos = None;
+        for &pos in &self.positions {
+            score = step_score(&self.config, score, pos, last_pos, c[pos].2);
+            last_pos = Some(pos);
+        }
+        self.byte_positions
+            .extend(self.positions.iter().map(|&j| c[j].0));
+        Some(score)
+    }
+
+    /// Scores the query as one contiguous run of characters in `candi`, placed
+    /// where `anchor` allows, keeping the best-scoring occurrence. Like any match
+    /// without typos, it ranks above the matches with typos.
+    ///
+    /// Returns `None` if `candi` has no such occurrence.
+    pub(crate) fn score_exact(&mut self, candi: &str, anchor: Anchor) -> Option<usize> {
+        if self.chars.is_empty() {
+            return None;
+        }
+        self.positions.clear();
+        self.byte_positions.clear();
+        self.typos = 0;
+        self.load(candi);
+
+        let n = self.chars.len();
+        let m = self.candi.len();
+        if n > m {
+            return None;
+        }
+        let starts = match anchor {
+            Anchor::Anywhere => 0..=m - n,
+            Anchor::Start => 0..=0,
+            Anchor::End => m - n..=m - n,
+            Anchor::Whole if n == m => 0..=0,
+            Anchor::Whole => return None,
+        };
+
+        let (total, start, tier) = self.best_run(candi, starts)?;
+        self.place_run(start);
+        self.tier = tier;
+        let score = self.normalize(total);
+        Some(self.above_typos(score))
+    }
+
+    /// Returns the start of the first contiguous occurrence of the query in the
+    /// loaded candidate, scanning it once (Knuth-Morris-Pratt).
+    fn first_run(&self) -> Option<usize> {
+        let n = self.chars.len();
+        let mut matched = 0;
+        for (j, &(_, cc, _)) in self.candi.iter().enumerate() {
+            while matched > 0 && self.chars[matched] != cc {
+                matched = self.failure[matched - 1];
+            }
+            if self.chars[matched] == cc {
+                matched += 1;
+            }
+            if matched == n {
+                return Some(j + 1 - n);
+            }
+        }
+        None
+    }
+
+    /// Finds the best-scoring contiguous occurrence of the query in the loaded
+    /// candidate among the given start positions.
+    ///
+    /// Returns the raw score of the occurrence plus its tier bonus, its start
+    /// and its tier, or `None` if there is no occurrence.
+    fn best_run(
+        &self,
+        candi: &str,
+        starts: std::ops::RangeInclusive<usize>,
+    ) -> Option<(usize, usize, Tier)> {
+        let n = self.chars.len();
+        let m = self.candi.len();
+        let mut best: Option<(usize, usize, Tier)> = None;
+        for start in starts {
+            let run = &self.candi[start..start + n];
+            if !run
+                .iter()
+                .zip(&self.chars)
+                .all(|(&(_, cc, _), &qc)| cc == qc)
+            {
+                continue;
+            }
+            let tier = match start {
+                0 if n == m && candi == self.query => Tier::Exact,
+                0 if n == m => Tier::FoldedExact,
+                0 => Tier::Prefix,
+                _ if starts_word(&self.config, candi, run[0].0) => Tier::WordPrefix,
+                _ => Tier::Substring,
+            };
+            let mut score = 0;
+            for (j, &(_, _, bonus)) in (start..).zip(run) {
+                let last_pos = (j > start).then(|| j - 1);
+                score = step_score(&self.config, score, j, last_pos, bonus);
+            }
+            score += self.tier_bonus(tier);
+            if best.is_none_or(|(b, _, _)| score > b) {
+                best = Some((score, start, tier));
+            }
+        }
+        best
+    }
+
+    /// Replaces the matched positions by the `n` chars from `start` on.
+    fn place_run(&mut self, start: usize) {
+        let end = start + self.chars.len();
+        self.positions.clear();
+        self.positions.extend(start..end);
+        self.byte_positions.clear();
+        self.byte_positions
+            .extend(self.candi[start..end].iter().map(|&(pos, _, _)| pos));
+    }
+
+    /// Returns the bonus a match of the given tier earns.
+    fn tier_bonus(&self, tier: Tier) -> usize {
+        let config = &self.config;
+        let per_char = match tier {
+            Tier::Exact => config.exact_bonus,
+            Tier::FoldedExact => config.folded_exact_bonus,
+            Tier::Prefix => config.prefix_bonus,
+            Tier::WordPrefix => config.word_prefix_bonus,
+            Tier::Substring => config.substring_bonus,
+            Tier::Scattered => 0,
+        };
+        per_char * self.chars.len()
+    }
+
+    /// Fills `self.candi` with the (byte offset, folded char, boundary bonus) of
+    /// every char of `candi`, and returns the candidate's char-set mask.
+    fn load(&mut self, candi: &str) -> u64 {
+        let config = &self.config;
+        let mut prev_char = None;
+        let mut mask = 0;
+        let basename = basename_start(config, candi);
+        self.candi.clear();
+        self.candi.extend(candi.char_indices().map(|(pos, cc)| {
+            let bonus = char_bonus(config, prev_char.replace(cc), cc, pos >= basename);
+            let cc = fold_if(cc, self.fold);
+            mask |= char_bit(cc);
+            (pos, cc, bonus)
+        }));
+        mask
+    }
+
+    /// Keeps a raw score including bonuses in `self.raw` and maps it onto the
+    /// `0..=max_score` range.
+    fn normalize(&mut self, score: usize) -> usize {
+        self.raw = score;
+        self.scale(score)
+    }
+
+    /// Raw points that map to `max_score`.
+    fn max_raw(&self) -> usize {
+        (self.chars.len() * self.config.char_score).max(1)
+    }
+
+    /// Maps a raw score including bonuses onto the `0..=max_score` range.
+    ///
+    /// Only exact matches, with or without case folding, reach `max_score`.
+    fn scale(&self, score: usize) -> usize {
+        let max_score = self.config.max_score;
+        let top = match self.tier {
+            Tier::Exact | Tier::FoldedExact => max_score,
+            _ => max_score.saturating_sub(1),
+        };
+        ((score * max_score) / self.max_raw()).min(top)
+    }
+}
+
+/// How closely a match follows the query, from best to worst.
+///
+/// Each tier earns its own bonus per query character, see [`ScoringConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Tier {
+    /// The candidate equals the query.
+    Exact,
+    /// The candidate equals the query after case folding.
+    FoldedExact,
+    /// The candidate starts with the query.
+    Prefix,
+    /// The query appears contiguously, starting at a word boundary.
+    WordPrefix,
+    /// The query appears contiguously inside a word.
+    Substring,
+    /// The query chars are spread out in the candidate.
+    #[default]
+    Scattered,
+}
+
+impl fmt::Display for Tier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Tier::Exact => "exact",
+            Tier::FoldedExact => "case-insensitive exact",
+            Tier::Prefix => "prefix",
+            Tier::WordPrefix => "word prefix",
+            Tier::Substring => "substring",
+            Tier::Scattered => "scattered",
+        })
+    }
+}
+
+/// Where a contiguous run of the query has to sit in the candidate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Anchor {
+    Anywhere,
+    Start,
+    End,
+    Whole,
+}
+
+/// Maps the score of a match that needed `typos` typos below `max_score / 4`,
+/// under every match without typos.
+pub(crate) fn typo_score(max_score: usize, score: usize, typos: usize) -> usize {
+    let ceiling = max_score / 4;
+    score * ceiling.saturating_sub(1) / max_score.max(1) / typos.max(1)
+}
+
+/// Adds the contribution of a character matched at char index `pos` to the running `score`,
+/// given the index of the previously matched character and the boundary bonus of `pos`.
+fn step_score(
+    config: &ScoringConfig,
+    score: usize,
+    pos: usize,
+    last_pos: Option<usize>,
+    bonus: usize,
+) -> usize {
+    step(config, score, pos, last_pos, bonus).running
+}
+
+/// Breaks down the contribution of a character matched at char index `pos`,
+/// like [`step_score`].
+fn step(
+    config: &ScoringConfig,
+    score: usize,
+    pos: usize,
+    last_pos: Option<usize>,
+    bonus: usize,
+) -> CharScore {
+    let weight = match config.position_decay {
+        PositionDecay::Linear(step) => config
+            .position_weight
+            .saturating_sub(pos.saturating_mul(step)),
+        PositionDecay::Hyperbolic(half) => {
+            (config.position_weight * half).div_ceil((half + pos).max(1))
+        }
+    };
+    let score = score + weight + bonus;
+    let (consecutive_bonus, gap_penalty) = match last_pos {
+        Some(lp) if pos == lp + 1 => (score * config.consecutive_bonus / 100, 0),
+        Some(lp) => (0, score.min(config.gap_penalty * (pos - lp - 1))),
+        None => (0, 0),
+    };
+    CharScore {
+        position: pos,
+        position_weight: weight,
+        boundary_bonus: bonus,
+        consecutive_bonus,
+        gap_penalty,
+        running: score + consecutive_bonus - gap_penalty,
+        ..Default::default()
+    }
+}
+
+/// Returns the bonus for matching `cur`, given the candidate character before it
+/// and whether `cur` is part of the basename in `path` mode.
+fn char_bonus(config: &ScoringConfig, prev: Option<char>, cur: char, basename: bool) -> usize {
+    let bonus = match prev {
+        None if config.path => config.start_bonus + config.segment_bonus,
+        None => config.start_bonus,
+        Some(p) if config.path && is_path_separator(p) => {
+            config.boundary_bonus + config.segment_bonus
+        }
+        Some(p) if is_separator(p) => config.boundary_bonus,
+        Some(p) if p.is_lowercase() && cur.is_uppercase() => config.camel_bonus,
+        _ => 0,
+    };
+    if basename {
+        bonus + config.basename_bonus
+    } else {
+        bonus
+    }
+}
+
+/// Returns the byte offset where the last path segment of `candi` starts in
+/// `path` mode, or `usize::MAX` otherwise.
+fn basename_start(config: &ScoringConfig, candi: &str) -> usize {
+    if !config.path {
+        return usize::MAX;
+    }
+    candi.rfind(is_path_separator).map_or(0, |pos| pos + 1)
+}
+
+/// Returns whether the char at byte offset `pos` of `candi` starts a word.
+fn starts_word(config: &ScoringConfig, candi: &str, pos: usize) -> bool {
+    let prev = candi[..pos].chars().next_back();
+    let cur = candi[pos..].chars().next();
+    match (prev, cur) {
+        (None, _) => true,
+        (Some(p), _) if is_separator(p) => true,
+        (Some(p), _) if config.path && is_path_separator(p) => true,
+        (Some(p), Some(c)) => p.is_lowercase() && c.is_uppercase(),
+        (Some(_), None) => false,
+    }
+}
+
+/// Returns whether `c` separates words in a candidate.
+fn is_separator(c: char) -> bool {
+    matches!(c, ' ' | '-' | '_' | '/' | '.')
+}
+
+/// Returns whether `c` separates path segments in `path` mode.
+fn is_path_separator(c: char) -> bool {
+    matches!(c, '/' | '\\')
+}
+
+/// Maps `c` to one of 64 buckets of the char-set prefilter.
+///
+/// Collisions only let a few non-matching candidates through to the full scorer.
+fn char_bit(c: char) -> u64 {
+    1 << (c as u32 % 64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CaseMode;
+
+    #[test]
+    fn test_matcher_agrees_with_free_functions() {
+        let candidates = ["xbps-install", "xargs-b", "a_xab", "grep", "XBPS", ""];
+        for algorithm in [Algorithm::Greedy, Algorithm::Optimal] {
+            for case in [CaseMode::Sensitive, CaseMode::Smart] {
+                let config = ScoringConfig {
+                    algorithm,
+                    case,
+                    ..Default::default()
+                };
+                let mut matcher = Matcher::with_config("xb", config);
+                for candi in candidates {
+                    assert_eq!(
+                        matcher.score(candi),
+                        crate::score_with("xb", candi, &config)
+                    );
+                    assert_eq!(matcher.find(candi), crate::match_with("xb", candi, &config));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_greedy_takes_first_contiguous_run() {
+        let mut greedy = Matcher::new("ab");
+        let mut optimal = Matcher::with_config(
+            "ab",
+            ScoringConfig {
+                algorithm: Algorithm::Optimal,
+                ..Default::default()
+            },
+        );
+        let found = greedy.find("xab ab").unwrap();
+        assert_eq!(found.positions, [1, 2]);
+        assert_eq!(greedy.explain("xab ab").unwrap().tier, Tier::Substring);
+        assert_eq!(optimal.find("xab ab").unwrap().positions, [4, 5]);
+        assert_eq!(optimal.explain("xab ab").unwrap().tier, Tier::WordPrefix);
+
+        // Partial runs that overlap the real one are not skipped past.
+        for (query, candi, start) in [("aab", "aaab", 1), ("abab", "abaabab", 3)] {
+            let positions: Vec<_> = (start..start + query.len()).collect();
+            assert_eq!(
+                Matcher::new(query).find(candi).unwrap().positions,
+                positions
+            );
+        }
+    }
+
+    #[test]
+    fn test_tokens_match_in_any_order() {
+        let config = ScoringConfig {
+            tokenize: true,
+            ..Default::default()
+        };
+        let mut matcher = Matcher::with_config("push git", config);
+        assert_eq!(crate::score("push git", "git push"), 0);
+        let m = matcher.find("git push").unwrap();
+        assert_eq!(m.positions, vec![4, 5, 6, 7, 0, 1, 2]);
+        assert_eq!(
+            m.score,
+            (crate::score("push", "git push") + crate::score("git", "git push")) / 2
+        );
+        assert_eq!(matcher.score("git pull"), 0);
+        assert_eq!(
+            Matcher::with_config("git push", config).score("git push"),
+            100
+        );
+
+        let mut single = Matcher::with_config("  gp ", config);
+        assert_eq!(single.score("git push"), crate::score("gp", "git push"));
+    }
+
+    #[test]
+    fn test_tokens_overlap_penalty() {
+        let config = ScoringConfig {
+            tokenize: true,
+            overlap_penalty: 10,
+            ..Default::default()
+        };
+        // Both terms can only use the same "ab" run.
+        let mut overlapping = Matcher::with_config("ab ab", config);
+        let mut separate = Matcher::with_config("ab cd", config);
+        let overlapped = overlapping.score("ab-cd");
+        let apart = separate.score("ab-cd");
+        let no_penalty = ScoringConfig {
+            overlap_penalty: 0,
+            ..config
+        };
+        assert_eq!(
+            overlapped + 20,
+            Matcher::with_config("ab ab", no_penalty).score("ab-cd")
+        );
+        assert!(apart > overlapped);
+    }
+
+    #[test]
+    fn test_matcher_reuses_buffers() {
+        let config = ScoringConfig {
+            algorithm: Algorithm::Optimal,
+            ..Default::default()
+        };
+        let mut matcher = Matcher::with_config("ab", config);
+        let long = format!("{}ab", "x".repeat(200));
+        assert!(matcher.score(&long) > 0);
+        let capacity = matcher.back.capacity();
+        assert_eq!(matcher.find("ab").unwrap().positions, vec![0, 1]);
+        assert_eq!(matcher.find("xaxb").unwrap().positions, vec![1, 3]);
+        assert_eq!(matcher.back.capacity(), capacity);
+        assert!(matcher.find("ba").is_none());
+        assert!(matcher.find("xyz").is_none());
+    }
+
+    #[test]
+    fn test_path_mode_prefers_basename() {
+        let path = ScoringConfig {
+            path: true,
+            ..Default::default()
+        };
+        let items = ["src/index/components.tsx", "src/components/index.tsx"];
+        assert_eq!(crate::match_items("index", &items)[0].0, items[0]);
+        assert_eq!(
+            crate::match_items_with("index", &items, &path)[0].0,
+            items[1]
+        );
+
+        let mut matcher = Matcher::with_config("main", path);
+        let windows = matcher.find("src\\bin\\main.rs").unwrap();
+        assert_eq!(windows.positions, vec![8, 9, 10, 11]);
+        assert_eq!(windows.score, matcher.score("src/bin/main.rs"));
+        assert!(
+            matcher.find("src/bin/main.rs").unwrap().raw
+                > matcher.find("main/src/lib.rs").unwrap().raw
+        );
+    }
+
+    #[test]
+    fn test_path_query_matches_segment_by_segment() {
+        let path = ScoringConfig {
+            path: true,
+            ..Default::default()
+        };
+        let mut matcher = Matcher::with_config("src/cfg", path);
+        // Both query segments must fit inside a path segment each, in order.
+        assert!(matcher.find("s/rc/cfg").is_none());
+        assert!(matcher.find("lib/src_cfg.rs").is_none());
+        assert!(matcher.find("cfg/src").is_none());
+        let m = matcher.find("app/src/x/config.rs").unwrap();
+        assert_eq!(m.positions, vec![4, 5, 6, 10, 13, 15]);
+        assert_eq!(m.byte_positions, m.positions);
+        assert_eq!(
+            m.score,
+            (crate::score("src", "src") + crate::score("cfg", "config.rs")) / 2
+        );
+        assert!(crate::score("src/cfg", "s/rc/cfg") > 0);
+
+        // Only the path equal to the query scores 100.
+        let items = ["x/src/lib.rs", "src/lib.rs", "src"];
+        let ranked = crate::match_items_with("src/lib.rs", &items, &path);
+        assert_eq!(ranked[0], ("src/lib.rs", 100));
+        assert!(ranked[1..].iter().all(|&(_, score)| score < 100));
+        let ranked = crate::match_items_with("src/", &["src/lib.rs", "src"], &path);
+        assert!(ranked.iter().all(|&(_, score)| score < 100));
+
+        // A query made of separators only is matched as a plain query.
+        assert!(Matcher::with_config("/", path).find("a/b").is_some());
+    }
+
+    #[test]
+    fn test_typos_tolerated() {
+        assert_eq!(crate::score("gti", "git"), 0);
+        let config = ScoringConfig {
+            max_typos: 1,
+            ..Default::default()
+        };
+        let mut matcher = Matcher::with_config("gti", config);
+        let m = matcher.find("git").unwrap();
+        assert_eq!((m.positions, m.typos), (vec![0, 1, 2], 1));
+        assert!(matcher.score("git") > 0);
+        // Two typos, or a query char missing from the candidate, are too many.
+        assert!(matcher.find("gxx").is_none());
+        assert!(matcher.find("").is_none());
+
+        let m = Matcher::with_config("xpbs", config)
+            .find("xbps-install")
+            .unwrap();
+        assert_eq!((m.positions, m.typos), (vec![0, 1, 2, 3], 1));
+
+        // Short queries never tolerate typos.
+        assert!(Matcher::with_config("gt", config).find("tg").is_none());
+    }
+
+    #[test]
+    fn test_typos_rank_below_exact_matches() {
+        let config = ScoringConfig {
+            max_typos: 2,
+            ..Default::default()
+        };
+        let mut matcher = Matcher::with_config("gti", config);
+        let far = format!("{}g{}t{}i", "x".repeat(40), "x".repeat(40), "x".repeat(40));
+        assert!(matcher.score(&far) > matcher.score("git"));
+        assert_eq!(matcher.score("gti"), 100);
+
+        // Averaging terms or segments keeps a typo below every clean match.
+        let x = |n| "x".repeat(n);
+        for (mode, query, typo, clean) in [
+            (
+                "tokenize",
+                "push gti",
+                "push git",
+                format!("p{}ush{} g{}ti", x(12), x(16), x(21)),
+            ),
+            (
+                "path",
+                "src/gti",
+                "src/git",
+                format!("{0}s{0}r{0}c/{0}g{0}t{0}i", x(4)),
+            ),
+        ] {
+            let config = ScoringConfig {
+                max_typos: 1,
+                tokenize: mode == "tokenize",
+                path: mode == "path",
+                ..Default::default()
+            };
+            let mut matcher = Matcher::with_config(query, config);
+            assert_eq!(matcher.find(typo).unwrap().typos, 1, "{mode}");
+            assert_eq!(matcher.find(&clean).unwrap().typos, 0, "{mode}");
+            assert!(matcher.score(typo) < matcher.score(&clean), "{mode}");
+        }
+
+        let mut long = Matcher::with_config("install", config);
+        let one = long.find("instlal").unwrap();
+        let two = long.find("isntlal").unwrap();
+        assert_eq!((one.typos, two.typos), (1, 2));
+        assert!(one.score > two.score);
+    }
+}
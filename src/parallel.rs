@@ -0,0 +1,189 @@
+use std::num::NonZeroUsize;
+use std::thread;
+
+use crate::filter::{select, Tie};
+use crate::{MatchOptions, Matcher, ScoringConfig};
+
+/// Smallest shard worth handing to its own thread.
+const MIN_CHUNK: usize = 4096;
+
+/// Matches multiple `items` against the `query` on all available cores.
+///
+/// The result is identical to [`match_items`](crate::match_items), including
+/// the order of items with equal scores.
+///
+/// # Arguments
+///
+/// * `query` - The search query string slice.
+/// * `items` - Slice of string slices to be matched.
+///
+/// # Returns
+///
+/// A vector of tuples `(item, score)`, sorted by descending score.
+///
+/// # Examples
+///
+/// ```
+/// let items = ["fefe", "feature", "banana"];
+/// let results = matchr::par_match_items("fefe", &items);
+/// assert_eq!(results, matchr::match_items("fefe", &items));
+/// ```
+pub fn par_match_items<'a>(query: &str, items: &[&'a str]) -> Vec<(&'a str, usize)> {
+    par_match_items_with(query, items, &ScoringConfig::default())
+}
+
+/// Matches multiple `items` against the `query` using the given `config` on
+/// all available cores.
+///
+/// The result is identical to [`match_items_with`](crate::match_items_with),
+/// including the order of items with equal scores.
+///
+/// # Arguments
+///
+/// * `query` - The search query string slice.
+/// * `items` - Slice of string slices to be matched.
+/// * `config` - The scoring options.
+///
+/// # Returns
+///
+/// A vector of tuples `(item, score)`, sorted by descending score.
+pub fn par_match_items_with<'a>(
+    query: &str,
+    items: &[&'a str],
+    config: &ScoringConfig,
+) -> Vec<(&'a str, usize)> {
+    let scores = par_scores(query, items, config, available_threads());
+    let scored = items
+        .iter()
+        .zip(scores)
+        .map(|(item, (score, tie))| (*item, score.unwrap_or(0), tie));
+    let options = MatchOptions {
+        config: *config,
+        ..Default::default()
+    };
+    select(scored, &options)
+}
+
+/// Filters `items` like [`filter_items`](crate::filter_items) on all available cores.
+///
+/// # Arguments
+///
+/// * `query` - The search query string slice.
+/// * `items` - Slice of string slices to be matched.
+/// * `options` - The scoring options, threshold and limit.
+///
+/// # Returns
+///
+/// A vector of tuples `(item, score)`, sorted by descending score. Items with
+/// equal scores are ranked by their unnormalized score, then by `options.config.tie_break`, input order by default.
+pub fn par_filter_items<'a>(
+    query: &str,
+    items: &[&'a str],
+    options: &MatchOptions,
+) -> Vec<(&'a str, usize)> {
+    let scores = par_scores(query, items, &options.config, available_threads());
+    let scored = items
+        .iter()
+        .zip(scores)
+        .filter_map(|(item, (score, tie))| Some((*item, score?, tie)));
+    select(scored, options)
+}
+
+fn available_threads() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+/// Scores every item, splitting `items` into at most `threads` contiguous shards
+/// that are scored concurrently and concatenated back in input order.
+///
+/// Returns a `None` score for items the query does not match, and the tie-break
+/// keys of every item.
+fn par_scores(
+    query: &str,
+    items: &[&str],
+    config: &ScoringConfig,
+    threads: usize,
+) -> Vec<(Option<usize>, Tie)> {
+    let chunk = items.len().div_ceil(threads.max(1)).max(MIN_CHUNK);
+    let matcher = Matcher::with_config(query, *config);
+    if items.len() <= chunk {
+        return score_chunk(matcher, items);
+    }
+
+    thread::scope(|scope| {
+        let shards: Vec<_> = items
+            .chunks(chunk)
+            .map(|shard| {
+                let matcher = matcher.clone();
+                scope.spawn(move || score_chunk(matcher, shard))
+            })
+            .collect();
+        shards
+            .into_iter()
+            .flat_map(|shard| shard.join().expect("scoring thread panicked"))
+            .collect()
+    })
+}
+
+fn score_chunk(mut matcher: Matcher, items: &[&str]) -> Vec<(Option<usize>, Tie)> {
+    items
+        .iter()
+        .map(|item| {
+            let score = matcher.try_score(item);
+            (score, Tie::new(&matcher, item, score.is_some()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{filter_items, match_items_with, Algorithm};
+
+    fn corpus() -> Vec<String> {
+        (0..3 * MIN_CHUNK + 17)
+            .map(|i| match i % 4 {
+                0 => format!("xbps-{i}"),
+                1 => format!("grep-{}", i % 7),
+                2 => format!("x/{i}/b"),
+                _ => format!("bin/{}", i % 3),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_par_scores_keep_input_order() {
+        let owned = corpus();
+        let items: Vec<&str> = owned.iter().map(String::as_str).collect();
+        let config = ScoringConfig::default();
+        let sequential = par_scores("xb", &items, &config, 1);
+        for threads in [2, 3, 8] {
+            assert_eq!(par_scores("xb", &items, &config, threads), sequential);
+        }
+    }
+
+    #[test]
+    fn test_par_matches_sequential() {
+        let owned = corpus();
+        let items: Vec<&str> = owned.iter().map(String::as_str).collect();
+        for algorithm in [Algorithm::Greedy, Algorithm::Optimal] {
+            let config = ScoringConfig {
+                algorithm,
+                ..Default::default()
+            };
+            assert_eq!(
+                par_match_items_with("xb1", &items, &config),
+                match_items_with("xb1", &items, &config)
+            );
+            let options = MatchOptions {
+                config,
+                limit: Some(50),
+                ..Default::default()
+            };
+            assert_eq!(
+                par_filter_items("xb1", &items, &options),
+                filter_items("xb1", &items, &options)
+            );
+        }
+    }
+}
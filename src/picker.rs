@@ -0,0 +1,617 @@
+// Outside Unix only the picker's state is compiled, for its tests.
+#![cfg_attr(not(unix), allow(dead_code))]
+
+use std::borrow::Cow;
+use std::fmt::Write as _;
+use std::io;
+
+use crate::{filter_by_key, highlight_ansi, AnsiStyle, Fields, MatchOptions, Matcher};
+
+/// Options for a [`Picker`].
+///
+/// # Examples
+///
+/// ```
+/// use matchr::PickerOptions;
+///
+/// let options = PickerOptions {
+///     prompt: "branch> ".into(),
+///     multi: true,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PickerOptions {
+    /// How items are scored, filtered and limited.
+    pub options: MatchOptions,
+    /// Text shown before the query. Default: `"> "`.
+    pub prompt: String,
+    /// The query the picker starts with. Default: empty, every item is listed.
+    pub query: String,
+    /// Maximum number of list rows, shrunk to fit the terminal. Default: `10`.
+    pub height: usize,
+    /// Lets Tab / Shift-Tab mark several items. Default: `false`.
+    pub multi: bool,
+    /// Restricts matching to these fields of every item, like fzf's `--nth`.
+    /// Default: `None`, the whole item is matched.
+    pub nth: Option<Fields>,
+}
+
+impl Default for PickerOptions {
+    fn default() -> Self {
+        PickerOptions {
+            options: MatchOptions::default(),
+            prompt: "> ".to_string(),
+            query: String::new(),
+            height: 10,
+            multi: false,
+            nth: None,
+        }
+    }
+}
+
+/// An interactive fuzzy picker drawn on the terminal, below the cursor.
+///
+/// Typing edits the query and re-ranks the list; matched characters are
+/// highlighted. Up / Ctrl-P and Down / Ctrl-N move the cursor, Tab and
+/// Shift-Tab mark items in `multi` mode, Enter accepts and Esc or Ctrl-C cancels.
+/// Backspace, Ctrl-U and Ctrl-W edit the query.
+///
+/// The picker talks to `/dev/tty`, so stdin and stdout stay free for piping,
+/// and switches it to raw mode with `stty`. It is only available on Unix.
+///
+/// # Examples
+///
+/// ```no_run
+/// use matchr::Picker;
+///
+/// let branches = ["main", "feature/picker", "fix/typo"];
+/// if let Some(chosen) = Picker::new(&branches).run()? {
+///     println!("{}", chosen.join(" "));
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Picker<'a> {
+    items: &'a [&'a str],
+    options: PickerOptions,
+}
+
+impl<'a> Picker<'a> {
+    /// Creates a picker over `items` with the default [`PickerOptions`].
+    pub fn new(items: &'a [&'a str]) -> Self {
+        Self::with_options(items, PickerOptions::default())
+    }
+
+    /// Creates a picker over `items` with the given `options`.
+    pub fn with_options(items: &'a [&'a str], options: PickerOptions) -> Self {
+        Picker { items, options }
+    }
+
+    /// Shows the picker and waits until the user accepts or cancels.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(Some(items))` when the user pressed Enter: the marked items in input
+    /// order, or else the item under the cursor (empty if nothing matched).
+    /// `Ok(None)` when the user cancelled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the terminal cannot be opened or switched to raw mode.
+    pub fn run(&self) -> io::Result<Option<Vec<&'a str>>> {
+        let chosen = self.run_indices()?;
+        Ok(chosen.map(|indices| indices.into_iter().map(|i| self.items[i]).collect()))
+    }
+
+    /// Shows the picker like [`Picker::run`], but returns the indices of the
+    /// chosen items, e.g. to look up the records they were rendered from.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the terminal cannot be opened or switched to raw mode,
+    /// and an [`Unsupported`](io::ErrorKind::Unsupported) error outside Unix.
+    pub fn run_indices(&self) -> io::Result<Option<Vec<usize>>> {
+        #[cfg(unix)]
+        return self.run_in(Terminal::open()?);
+        #[cfg(not(unix))]
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the picker needs a Unix terminal",
+        ));
+    }
+
+    /// Runs the picker on an open `terminal` until the user accepts or cancels.
+    #[cfg(unix)]
+    fn run_in(&self, mut terminal: Terminal) -> io::Result<Option<Vec<usize>>> {
+        let (rows, width) = terminal.size()?;
+        let height = self.options.height.min(rows.saturating_sub(2)).max(1);
+        let mut state = State::new(self.items, &self.options);
+
+        let mut buf = [0; 64];
+        loop {
+            terminal.draw(&state.render(height, width), &state.cursor_column())?;
+            let read = terminal.read(&mut buf)?;
+            if read == 0 {
+                terminal.clear()?;
+                return Ok(None);
+            }
+            for key in parse_keys(&buf[..read]) {
+                if let Some(outcome) = state.handle(key, height) {
+                    terminal.clear()?;
+                    return Ok(match outcome {
+                        Outcome::Accept => Some(state.chosen()),
+                        Outcome::Cancel => None,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// A key press decoded from terminal input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Char(char),
+    Backspace,
+    ClearQuery,
+    DeleteWord,
+    Up,
+    Down,
+    Tab,
+    BackTab,
+    Enter,
+    Cancel,
+}
+
+/// How the user left the picker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Accept,
+    Cancel,
+}
+
+/// Decodes the keys in one chunk of raw terminal input.
+///
+/// A terminal sends an escape sequence in one write, so a chunk made of a lone
+/// ESC is the Esc key.
+fn parse_keys(input: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let text = String::from_utf8_lossy(input);
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let key = match c {
+            '\x1b' if chars.peek() == Some(&'[') || chars.peek() == Some(&'O') => {
+                chars.next();
+                // Skip parameters such as the `5` of PageUp's `ESC [ 5 ~`.
+                let final_byte = chars.find(|c| !('\x20'..='\x3f').contains(c));
+                match final_byte {
+                    Some('A') => Key::Up,
+                    Some('B') => Key::Down,
+                    Some('Z') => Key::BackTab,
+                    _ => continue,
+                }
+            }
+            '\x1b' | '\x03' | '\x07' => Key::Cancel,
+            '\r' | '\n' => Key::Enter,
+            '\t' => Key::Tab,
+            '\x7f' | '\x08' => Key::Backspace,
+            '\x15' => Key::ClearQuery,
+            '\x17' => Key::DeleteWord,
+            '\x10' => Key::Up,
+            '\x0e' => Key::Down,
+            c if c.is_control() => continue,
+            c => Key::Char(c),
+        };
+        keys.push(key);
+    }
+    keys
+}
+
+/// The query, ranked list, cursor and marks of a running picker.
+struct State<'a, 'o> {
+    items: &'a [&'a str],
+    options: &'o PickerOptions,
+    entries: Vec<(usize, &'a str)>,
+    query: String,
+    // (item index, score) of the listed items, best first.
+    results: Vec<(usize, usize)>,
+    cursor: usize,
+    offset: usize,
+    marked: Vec<bool>,
+}
+
+impl<'a, 'o> State<'a, 'o> {
+    fn new(items: &'a [&'a str], options: &'o PickerOptions) -> Self {
+        let mut state = State {
+            items,
+            options,
+            entries: items.iter().copied().enumerate().collect(),
+            query: options.query.clone(),
+            results: Vec::new(),
+            cursor: 0,
+            offset: 0,
+            marked: vec![false; items.len()],
+        };
+        state.refresh();
+        state
+    }
+
+    /// Re-ranks the items for the current query and moves the cursor to the top.
+    fn refresh(&mut self) {
+        let options = &self.options.options;
+        self.results = if self.query.is_empty() {
+            let limit = options.limit.unwrap_or(usize::MAX);
+            (0..self.items.len()).map(|i| (i, 0)).take(limit).collect()
+        } else {
+            filter_by_key(
+                &self.query,
+                &self.entries,
+                |entry| self.key(entry.1),
+                options,
+            )
+            .into_iter()
+            .map(|(&(index, _), score)| (index, score))
+            .collect()
+        };
+        self.cursor = 0;
+        self.offset = 0;
+    }
+
+    /// Applies `key`, keeping the cursor inside the `height` visible rows.
+    fn handle(&mut self, key: Key, height: usize) -> Option<Outcome> {
+        match key {
+            Key::Char(c) => {
+                self.query.push(c);
+                self.refresh();
+            }
+            Key::Backspace => {
+                if self.query.pop().is_some() {
+                    self.refresh();
+                }
+            }
+            Key::ClearQuery => {
+                self.query.clear();
+                self.refresh();
+            }
+            Key::DeleteWord => {
+                let kept = self.query.trim_end().rfind(' ').map_or(0, |i| i + 1);
+                self.query.truncate(kept);
+                self.refresh();
+            }
+            Key::Up => self.cursor = self.cursor.saturating_sub(1),
+            Key::Down => self.cursor = (self.cursor + 1).min(self.results.len().saturating_sub(1)),
+            Key::Tab | Key::BackTab if self.options.multi => {
+                if let Some(&(index, _)) = self.results.get(self.cursor) {
+                    self.marked[index] = !self.marked[index];
+                }
+                let step = if key == Key::Tab { Key::Down } else { Key::Up };
+                return self.handle(step, height);
+            }
+            Key::Tab | Key::BackTab => {}
+            Key::Enter => return Some(Outcome::Accept),
+            Key::Cancel => return Some(Outcome::Cancel),
+        }
+        if self.cursor < self.offset {
+            self.offset = self.cursor;
+        } else if self.cursor >= self.offset + height {
+            self.offset = self.cursor + 1 - height;
+        }
+        None
+    }
+
+    /// Returns the indices of the marked items in input order, or else the
+    /// index of the item under the cursor.
+    fn chosen(&self) -> Vec<usize> {
+        let marked: Vec<_> = (0..self.items.len()).filter(|&i| self.marked[i]).collect();
+        if !marked.is_empty() {
+            return marked;
+        }
+        self.results
+            .get(self.cursor)
+            .map(|&(index, _)| index)
+            .into_iter()
+            .collect()
+    }
+
+    /// Returns the text of `item` that the query is matched against.
+    fn key<'i>(&self, item: &'i str) -> Cow<'i, str> {
+        match &self.options.nth {
+            Some(nth) => nth.select(item),
+            None => Cow::Borrowed(item),
+        }
+    }
+
+    /// Returns the prompt line, a counter line and up to `height` list rows,
+    /// each cut to `width` chars, with the matched characters in bold.
+    fn render(&self, height: usize, width: usize) -> Vec<String> {
+        let mut lines = vec![format!("{}{}", self.options.prompt, self.query)];
+        let marked = self.marked.iter().filter(|&&m| m).count();
+        let mut counter = format!("  {}/{}", self.results.len(), self.items.len());
+        if self.options.multi {
+            let _ = write!(counter, " ({marked})");
+        }
+        lines.push(counter);
+
+        let mut matcher = Matcher::with_config(&self.query, self.options.options.config);
+        let visible = self
+            .results
+            .iter()
+            .enumerate()
+            .skip(self.offset)
+            .take(height);
+        for (row, &(index, _)) in visible {
+            let item = self.items[index];
+            let mut positions = matcher
+                .find(&self.key(item))
+                .map(|m| m.positions)
+                .unwrap_or_default();
+            if let Some(nth) = &self.options.nth {
+                positions = nth.map_positions(item, &positions);
+            }
+            let mut line = String::new();
+            line.push_str(if row == self.cursor { "> " } else { "  " });
+            if self.options.multi {
+                line.push_str(if self.marked[index] { "* " } else { "  " });
+            }
+            let room = width.saturating_sub(line.chars().count());
+            let shown: String = item
+                .chars()
+                .take(room)
+                .map(|c| if c.is_control() { ' ' } else { c })
+                .collect();
+            line.push_str(&highlight_ansi(&shown, &positions, &AnsiStyle::BOLD));
+            if row == self.cursor {
+                line = format!("\x1b[7m{line}\x1b[27m");
+            }
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// Returns the text before the terminal cursor on the prompt line.
+    fn cursor_column(&self) -> String {
+        format!("{}{}", self.options.prompt, self.query)
+    }
+}
+
+/// The controlling terminal in raw mode, restored when dropped.
+#[cfg(unix)]
+struct Terminal {
+    tty: std::fs::File,
+    saved: String,
+}
+
+#[cfg(unix)]
+impl Terminal {
+    fn open() -> io::Result<Self> {
+        let tty = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open("/dev/tty")?;
+        let saved = stty(&tty, &["-g"])?;
+        stty(&tty, &["raw", "-echo"])?;
+        Ok(Terminal {
+            tty,
+            saved: saved.trim().to_string(),
+        })
+    }
+
+    /// Returns the (rows, columns) of the terminal.
+    fn size(&self) -> io::Result<(usize, usize)> {
+        let size = stty(&self.tty, &["size"])?;
+        let mut dims = size.split_whitespace().map(str::parse);
+        match (dims.next(), dims.next()) {
+            (Some(Ok(rows)), Some(Ok(cols))) if rows > 0 && cols > 0 => Ok((rows, cols)),
+            _ => Ok((24, 80)),
+        }
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        io::Read::read(&mut self.tty, buf)
+    }
+
+    /// Redraws `lines` from the prompt line down, leaving the cursor after `prompt`.
+    fn draw(&mut self, lines: &[String], prompt: &str) -> io::Result<()> {
+        let mut frame = String::from("\r\x1b[J");
+        frame.push_str(&lines.join("\r\n"));
+        if lines.len() > 1 {
+            let _ = write!(frame, "\x1b[{}A", lines.len() - 1);
+        }
+        frame.push('\r');
+        let column = prompt.chars().count();
+        if column > 0 {
+            let _ = write!(frame, "\x1b[{column}C");
+        }
+        io::Write::write_all(&mut self.tty, frame.as_bytes())?;
+        io::Write::flush(&mut self.tty)
+    }
+
+    /// Erases the picker.
+    fn clear(&mut self) -> io::Result<()> {
+        io::Write::write_all(&mut self.tty, b"\r\x1b[J")?;
+        io::Write::flush(&mut self.tty)
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = stty(&self.tty, &[self.saved.as_str()]);
+    }
+}
+
+/// Runs `stty` on the terminal and returns its output.
+#[cfg(unix)]
+fn stty(tty: &std::fs::File, args: &[&str]) -> io::Result<String> {
+    let output = std::process::Command::new("stty")
+        .args(args)
+        .stdin(tty.try_clone()?)
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!("stty {} failed", args.join(" "))));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ITEMS: [&str; 6] = [
+        "xbps-install",
+        "grep",
+        "xbps-remove",
+        "xargs",
+        "xbps-query",
+        "bash",
+    ];
+
+    fn type_query<'a>(state: &mut State<'a, '_>, query: &str) {
+        for c in query.chars() {
+            state.handle(Key::Char(c), 3);
+        }
+    }
+
+    #[test]
+    fn test_parse_keys() {
+        assert_eq!(
+            parse_keys(b"ab\x1b[A\x1b[B\x1b[Z\t\r\x7f\x15\x17\x10\x0e"),
+            [
+                Key::Char('a'),
+                Key::Char('b'),
+                Key::Up,
+                Key::Down,
+                Key::BackTab,
+                Key::Tab,
+                Key::Enter,
+                Key::Backspace,
+                Key::ClearQuery,
+                Key::DeleteWord,
+                Key::Up,
+                Key::Down,
+            ]
+        );
+        assert_eq!(parse_keys(b"\x1b"), [Key::Cancel]);
+        assert_eq!(parse_keys(b"\x03"), [Key::Cancel]);
+        assert_eq!(parse_keys("é".as_bytes()), [Key::Char('é')]);
+        assert_eq!(parse_keys(b"\x1bOA"), [Key::Up]);
+        assert_eq!(
+            parse_keys(b"\x1b[5~\x01\x1b[1;2Bq"),
+            [Key::Down, Key::Char('q')]
+        );
+    }
+
+    #[test]
+    fn test_query_ranks_like_filter_items() {
+        let options = PickerOptions::default();
+        let mut state = State::new(&ITEMS, &options);
+        assert_eq!(state.results.len(), ITEMS.len());
+        type_query(&mut state, "xbr");
+        let listed: Vec<_> = state.results.iter().map(|&(i, _)| ITEMS[i]).collect();
+        let expected: Vec<_> = crate::filter_items("xbr", &ITEMS, &options.options)
+            .into_iter()
+            .map(|(item, _)| item)
+            .collect();
+        assert_eq!(listed, expected);
+
+        state.handle(Key::Backspace, 3);
+        assert_eq!(state.query, "xb");
+        state.handle(Key::ClearQuery, 3);
+        assert_eq!(state.results.len(), ITEMS.len());
+        type_query(&mut state, "xb re");
+        state.handle(Key::DeleteWord, 3);
+        assert_eq!(state.query, "xb ");
+    }
+
+    #[test]
+    fn test_navigation_scrolls() {
+        let options = PickerOptions::default();
+        let mut state = State::new(&ITEMS, &options);
+        state.handle(Key::Up, 3);
+        assert_eq!((state.cursor, state.offset), (0, 0));
+        for _ in 0..4 {
+            state.handle(Key::Down, 3);
+        }
+        assert_eq!((state.cursor, state.offset), (4, 2));
+        for _ in 0..10 {
+            state.handle(Key::Down, 3);
+        }
+        assert_eq!(state.cursor, ITEMS.len() - 1);
+        for _ in 0..4 {
+            state.handle(Key::Up, 3);
+        }
+        assert_eq!((state.cursor, state.offset), (1, 1));
+        assert_eq!(state.chosen(), [1]);
+        assert_eq!(state.handle(Key::Enter, 3), Some(Outcome::Accept));
+        assert_eq!(state.handle(Key::Cancel, 3), Some(Outcome::Cancel));
+    }
+
+    #[test]
+    fn test_multi_select() {
+        let single = PickerOptions::default();
+        let mut state = State::new(&ITEMS, &single);
+        state.handle(Key::Tab, 3);
+        assert_eq!((state.cursor, state.chosen()), (0, vec![0]));
+
+        let multi = PickerOptions {
+            multi: true,
+            ..Default::default()
+        };
+        let mut state = State::new(&ITEMS, &multi);
+        type_query(&mut state, "xb");
+        let ranked: Vec<_> = state.results.iter().map(|&(i, _)| i).collect();
+        for key in [Key::Tab, Key::Down, Key::Tab, Key::Up, Key::Tab] {
+            state.handle(key, 3);
+        }
+        assert_eq!(state.cursor, 2);
+        state.handle(Key::Up, 3);
+        state.handle(Key::BackTab, 3);
+        assert_eq!(state.cursor, 0);
+        // Marks survive query edits and come back in input order.
+        state.handle(Key::ClearQuery, 3);
+        let mut expected = [ranked[0], ranked[2]];
+        expected.sort();
+        assert_eq!(state.chosen(), expected);
+
+        let mut empty = State::new(&ITEMS, &multi);
+        type_query(&mut empty, "zzz");
+        assert!(empty.chosen().is_empty());
+    }
+
+    #[test]
+    fn test_render_highlights_matches() {
+        let options = PickerOptions {
+            query: "gp".into(),
+            ..Default::default()
+        };
+        let state = State::new(&ITEMS, &options);
+        let lines = state.render(5, 80);
+        assert_eq!(lines[0], "> gp");
+        assert_eq!(lines[1], "  1/6");
+        assert_eq!(
+            lines[2],
+            "\x1b[7m> \x1b[1mg\x1b[22mre\x1b[1mp\x1b[22m\x1b[27m"
+        );
+        assert_eq!(state.cursor_column(), "> gp");
+
+        let narrow = State::new(&ITEMS, &PickerOptions::default()).render(2, 6);
+        assert_eq!(narrow.len(), 4);
+        assert_eq!(narrow[3], "  grep");
+    }
+
+    #[test]
+    fn test_nth_restricts_matching() {
+        let log = ["f1 add tests", "a2 fix typo"];
+        let options = PickerOptions {
+            query: "f".into(),
+            nth: Some(Fields::parse("2..", crate::Delimiter::Whitespace).unwrap()),
+            ..Default::default()
+        };
+        let state = State::new(&log, &options);
+        assert_eq!(state.chosen(), [1]);
+        let lines = state.render(5, 80);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[2], "\x1b[7m> a2 \x1b[1mf\x1b[22mix typo\x1b[27m");
+    }
+}
@@ -0,0 +1,450 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::filter::{select, Tie};
+use crate::matcher::{typo_score, Anchor};
+use crate::{MatchOptions, Matcher, ScoringConfig};
+
+/// A query in fzf's extended search syntax.
+///
+/// | Term      | Matches items that                          |
+/// |-----------|---------------------------------------------|
+/// | `sbtrkt`  | fuzzy match `sbtrkt`                        |
+/// | `'wild`   | contain `wild`                              |
+/// | `^music`  | start with `music`                          |
+/// | `.mp3$`   | end with `.mp3`                             |
+/// | `^core$`  | are exactly `core`                          |
+/// | `!fire`   | do not contain `fire`                       |
+/// | `!^music` | do not start with `music`                   |
+/// | `!.mp3$`  | do not end with `.mp3`                      |
+///
+/// Space-separated terms must all match; terms joined by ` | ` match if any of
+/// them does. A backslash makes the next character literal, e.g. `foo\ bar`
+/// or `\!important`.
+///
+/// The score of an item is the average over the AND-ed groups of the best
+/// score in each group. Negated terms only filter; a query made of negated
+/// terms alone gives every remaining item `config.max_score`.
+///
+/// # Examples
+///
+/// ```
+/// use matchr::ExtendedQuery;
+///
+/// let mut query = ExtendedQuery::parse("^src .rs$ | .toml$ !test").unwrap();
+/// assert!(query.score("src/lib.rs") > 0);
+/// assert!(query.score("src/Cargo.toml") > 0);
+/// assert_eq!(query.score("src/test.rs"), 0);
+/// assert_eq!(query.score("benches/lib.rs"), 0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExtendedQuery {
+    groups: Vec<Vec<Term>>,
+    max_score: usize,
+    // (group, term) index of the best-scoring term of the last matching candidate.
+    best: Option<(usize, usize)>,
+}
+
+#[derive(Debug, Clone)]
+struct Term {
+    kind: TermKind,
+    negated: bool,
+    matcher: Matcher,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TermKind {
+    Fuzzy,
+    Exact(Anchor),
+}
+
+/// Error returned when an extended query is malformed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    /// An operator (`!`, `'`, `^`, `$`) with no text to apply to, e.g. `!` or `^$`.
+    EmptyTerm(String),
+    /// A `|` with no term on one of its sides.
+    DanglingOr,
+    /// The query ends with a `\` that escapes nothing.
+    TrailingEscape,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::EmptyTerm(term) => write!(f, "term `{term}` has no text to match"),
+            QueryError::DanglingOr => write!(f, "`|` must have a term on both sides"),
+            QueryError::TrailingEscape => write!(f, "query ends with an unfinished `\\` escape"),
+        }
+    }
+}
+
+impl Error for QueryError {}
+
+impl ExtendedQuery {
+    /// Parses `query` with the default [`ScoringConfig`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`QueryError`] if the query is malformed.
+    pub fn parse(query: &str) -> Result<Self, QueryError> {
+        Self::parse_with(query, ScoringConfig::default())
+    }
+
+    /// Parses `query`, scoring every term with the given `config`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`QueryError`] if the query is malformed.
+    pub fn parse_with(query: &str, config: ScoringConfig) -> Result<Self, QueryError> {
+        let mut groups: Vec<Vec<Term>> = Vec::new();
+        let mut pending_or = false;
+
+        for word in split_words(query)? {
+            if word == [('|', false)] {
+                if pending_or || groups.is_empty() {
+                    return Err(QueryError::DanglingOr);
+                }
+                pending_or = true;
+                continue;
+            }
+            let term = parse_term(&word, config)?;
+            match groups.last_mut() {
+                Some(group) if pending_or => group.push(term),
+                _ => groups.push(vec![term]),
+            }
+            pending_or = false;
+        }
+        if pending_or {
+            return Err(QueryError::DanglingOr);
+        }
+
+        Ok(ExtendedQuery {
+            groups,
+            max_score: config.max_score,
+            best: None,
+        })
+    }
+
+    /// Scores how well `candi` satisfies the query.
+    ///
+    /// # Returns
+    ///
+    /// A usize score between 0 and `config.max_score`; 0 if any group fails to match.
+    pub fn score(&mut self, candi: &str) -> usize {
+        self.try_score(candi).unwrap_or(0)
+    }
+
+    /// Scores `candi` like [`ExtendedQuery::score`], but returns `None` instead
+    /// of 0 when the query does not match.
+    pub(crate) fn try_score(&mut self, candi: &str) -> Option<usize> {
+        self.best = None;
+        if self.groups.is_empty() {
+            return None;
+        }
+        let mut total = 0;
+        let mut typos = 0;
+        let mut scored_groups = 0;
+        // (score, group, term) of the best-scoring term over all groups.
+        let mut top: Option<(usize, usize, usize)> = None;
+        for (g, group) in self.groups.iter_mut().enumerate() {
+            // The best (score, typos, term) of the group's matching positive terms.
+            let mut best: Option<Option<(usize, usize, usize)>> = None;
+            for (t, term) in group.iter_mut().enumerate() {
+                if let Some(score) = term.score(candi) {
+                    let found = score.map(|score| (score, term.matcher.typos(), t));
+                    best = Some(best.flatten().max(found));
+                }
+            }
+            if let Some((score, term_typos, t)) = best? {
+                total += score;
+                typos += term_typos;
+                scored_groups += 1;
+                if top.is_none_or(|(s, _, _)| score > s) {
+                    top = Some((score, g, t));
+                }
+            }
+        }
+        self.best = top.map(|(_, g, t)| (g, t));
+        // As for a single pattern, a match with typos ranks below clean ones.
+        match scored_groups {
+            0 => Some(self.max_score),
+            n if typos > 0 => Some(typo_score(self.max_score, total / n, typos)),
+            n => Some(total / n),
+        }
+    }
+
+    /// Returns the matcher of the best-scoring term of the last matching
+    /// candidate, `None` if only negated terms matched it.
+    pub(crate) fn best_matcher(&self) -> Option<&Matcher> {
+        let (g, t) = self.best?;
+        Some(&self.groups[g][t].matcher)
+    }
+}
+
+/// Matches `items` against a `query` in the extended search syntax of
+/// [`ExtendedQuery`] and returns the matching items that reach
+/// `options.min_score`, best first, at most `options.limit` of them.
+///
+/// # Arguments
+///
+/// * `query` - The search query, in extended syntax.
+/// * `items` - Slice of items to be matched.
+/// * `key` - Returns the string to match for an item.
+/// * `options` - The scoring options, threshold and limit.
+///
+/// # Returns
+///
+/// A vector of tuples `(item, score)` borrowing from `items`, sorted by descending
+/// score. Items with equal scores are ranked like [`filter_items`](crate::filter_items),
+/// on the best-scoring term of the query.
+///
+/// # Errors
+///
+/// Returns a [`QueryError`] if the query is malformed.
+///
+/// # Examples
+///
+/// ```
+/// use matchr::{filter_extended, MatchOptions};
+///
+/// let files = ["src/lib.rs", "src/main.rs", "tests/lib.rs", "Cargo.toml"];
+/// let hits = filter_extended("^src .rs$ !main", &files, |f| *f, &MatchOptions::default())?;
+/// assert_eq!(hits, [(&"src/lib.rs", hits[0].1)]);
+/// # Ok::<(), matchr::QueryError>(())
+/// ```
+pub fn filter_extended<'a, T, K, F>(
+    query: &str,
+    items: &'a [T],
+    mut key: F,
+    options: &MatchOptions,
+) -> Result<Vec<(&'a T, usize)>, QueryError>
+where
+    F: FnMut(&'a T) -> K,
+    K: AsRef<str>,
+{
+    let mut parsed = ExtendedQuery::parse_with(query, options.config)?;
+    let tie_break = options.config.tie_break;
+    let scored = items.iter().filter_map(|item| {
+        let candi = key(item);
+        let score = parsed.try_score(candi.as_ref())?;
+        // Equal scores are ranked on the best-scoring term, as for a plain query.
+        let tie = match parsed.best_matcher() {
+            Some(matcher) => Tie::new(matcher, candi.as_ref(), true),
+            None => Tie::of_text(tie_break, candi.as_ref()),
+        };
+        Some((item, score, tie))
+    });
+    Ok(select(scored, options))
+}
+
+impl Term {
+    /// Returns `None` if the term rejects `candi`, `Some(None)` if a negated
+    /// term accepts it, and `Some(Some(score))` if a positive term matches.
+    fn score(&mut self, candi: &str) -> Option<Option<usize>> {
+        let score = match self.kind {
+            TermKind::Fuzzy => self.matcher.try_score(candi),
+            TermKind::Exact(anchor) => self.matcher.score_exact(candi, anchor),
+        };
+        match (self.negated, score) {
+            (false, Some(score)) => Some(Some(score)),
+            (true, None) => Some(None),
+            _ => None,
+        }
+    }
+}
+
+/// Splits `query` on unescaped whitespace into words of `(char, escaped)` pairs.
+fn split_words(query: &str) -> Result<Vec<Vec<(char, bool)>>, QueryError> {
+    let mut words = Vec::new();
+    let mut word = Vec::new();
+    let mut chars = query.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => word.push((chars.next().ok_or(QueryError::TrailingEscape)?, true)),
+            c if c.is_whitespace() => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push((c, false)),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+fn parse_term(word: &[(char, bool)], config: ScoringConfig) -> Result<Term, QueryError> {
+    let mut rest = word;
+    let negated = rest.first() == Some(&('!', false));
+    if negated {
+        rest = &rest[1..];
+    }
+    let exact = rest.first() == Some(&('\'', false));
+    let prefix = rest.first() == Some(&('^', false));
+    if exact || prefix {
+        rest = &rest[1..];
+    }
+    let suffix = rest.last() == Some(&('$', false));
+    if suffix {
+        rest = &rest[..rest.len() - 1];
+    }
+    if rest.is_empty() {
+        return Err(QueryError::EmptyTerm(
+            word.iter().map(|&(c, _)| c).collect(),
+        ));
+    }
+
+    let kind = match (prefix, suffix) {
+        (true, true) => TermKind::Exact(Anchor::Whole),
+        (true, false) => TermKind::Exact(Anchor::Start),
+        (false, true) => TermKind::Exact(Anchor::End),
+        (false, false) if exact || negated => TermKind::Exact(Anchor::Anywhere),
+        (false, false) => TermKind::Fuzzy,
+    };
+    let text: String = rest.iter().map(|&(c, _)| c).collect();
+    Ok(Term {
+        kind,
+        negated,
+        matcher: Matcher::with_config(
+            &text,
+            ScoringConfig {
+                tokenize: false,
+                ..config
+            },
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::score;
+
+    #[test]
+    fn test_term_kinds() {
+        let cases = [
+            ("sbtrkt", "subtracket", true),
+            ("'wild", "a wildcard", true),
+            ("'wild", "w-i-l-d", false),
+            ("^music", "music/rock.mp3", true),
+            ("^music", "my music", false),
+            (".mp3$", "music/rock.mp3", true),
+            (".mp3$", "rock.mp3.bak", false),
+            ("^core$", "core", true),
+            ("^core$", "core2", false),
+            ("!fire", "water", true),
+            ("!fire", "firewall", false),
+            ("!^music", "my music", true),
+            ("!.mp3$", "rock.mp3", false),
+            ("foo\\ bar", "foo bar", true),
+            ("foo\\ bar", "foobar", false),
+            ("\\!x", "!x", true),
+            ("\\!x", "y", false),
+        ];
+        for (query, candi, matches) in cases {
+            let mut parsed = ExtendedQuery::parse(query).unwrap();
+            assert_eq!(parsed.score(candi) > 0, matches, "{query} / {candi}");
+        }
+    }
+
+    #[test]
+    fn test_and_or_groups() {
+        let mut query = ExtendedQuery::parse("^core go$ | rb$ | py$").unwrap();
+        assert!(query.score("core/main.go") > 0);
+        assert!(query.score("core/app.py") > 0);
+        assert_eq!(query.score("core/app.rs"), 0);
+        assert_eq!(query.score("lib/main.go"), 0);
+    }
+
+    #[test]
+    fn test_scores_combine_term_scores() {
+        let mut single = ExtendedQuery::parse("xb").unwrap();
+        assert_eq!(single.score("xbps-install"), score("xb", "xbps-install"));
+
+        let mut both = ExtendedQuery::parse("xb inst").unwrap();
+        let expected = (score("xb", "xbps-install") + score("inst", "xbps-install")) / 2;
+        assert_eq!(both.score("xbps-install"), expected);
+
+        let mut negated = ExtendedQuery::parse("!grep").unwrap();
+        assert_eq!(negated.score("xbps-install"), 100);
+        assert_eq!(negated.score("grep"), 0);
+        assert_eq!(ExtendedQuery::parse("").unwrap().score("xbps"), 0);
+    }
+
+    #[test]
+    fn test_exact_terms_rank_above_typos() {
+        let config = ScoringConfig {
+            max_typos: 1,
+            ..Default::default()
+        };
+        let score = |query| {
+            ExtendedQuery::parse_with(query, config)
+                .unwrap()
+                .score("my git")
+        };
+        assert_eq!(score("'git"), score("git"));
+        assert_eq!(score("git$"), score("git"));
+        assert!(score("^m") >= 100 / 4);
+        assert!(score("gti") < 100 / 4);
+    }
+
+    #[test]
+    fn test_typo_terms_rank_below_clean_matches() {
+        let config = ScoringConfig {
+            max_typos: 1,
+            ..Default::default()
+        };
+        let mut query = ExtendedQuery::parse_with("push gti", config).unwrap();
+        let clean = format!(
+            "p{}ush{} g{}ti",
+            "x".repeat(12),
+            "x".repeat(16),
+            "x".repeat(21)
+        );
+        assert!(query.score("push git") > 0);
+        assert!(query.score("push git") < query.score(&clean));
+        assert!(query.score("push git") < 100 / 4);
+    }
+
+    #[test]
+    fn test_filter_extended_ranks_like_score() {
+        let items = ["xbps-install", "grep", "xbps-remove", "xargs"];
+        let options = MatchOptions {
+            limit: Some(2),
+            ..Default::default()
+        };
+        let hits = filter_extended("xb | ^gr !remove", &items, |i| *i, &options).unwrap();
+        let mut query = ExtendedQuery::parse("xb | ^gr !remove").unwrap();
+        let mut expected: Vec<_> = ["xbps-install", "grep"]
+            .iter()
+            .map(|item| (item, query.score(item)))
+            .collect();
+        expected.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits, expected);
+        assert!(filter_extended("foo |", &items, |i| *i, &options).is_err());
+    }
+
+    #[test]
+    fn test_malformed_queries() {
+        let cases = [
+            ("!", QueryError::EmptyTerm("!".into())),
+            ("foo ^$", QueryError::EmptyTerm("^$".into())),
+            ("'", QueryError::EmptyTerm("'".into())),
+            ("| foo", QueryError::DanglingOr),
+            ("foo |", QueryError::DanglingOr),
+            ("foo | | bar", QueryError::DanglingOr),
+            ("foo\\", QueryError::TrailingEscape),
+        ];
+        for (query, error) in cases {
+            assert_eq!(ExtendedQuery::parse(query).unwrap_err(), error, "{query}");
+        }
+        assert_eq!(
+            QueryError::DanglingOr.to_string(),
+            "`|` must have a term on both sides"
+        );
+    }
+}
@@ -0,0 +1,448 @@
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::filter::{select, Tie};
+use crate::{Match, MatchOptions, Matcher, ScoringConfig};
+
+/// Extracts the text of a field from a record of type `T`.
+type Key<T> = Box<dyn Fn(&T) -> Cow<'_, str>>;
+
+/// A string field of a record type `T`, scored with a relative weight.
+///
+/// The text of the field is borrowed from the record or built on the fly.
+///
+/// # Examples
+///
+/// ```
+/// use matchr::RecordField;
+///
+/// struct Command {
+///     name: String,
+///     keys: Vec<&'static str>,
+/// }
+///
+/// let fields = [
+///     RecordField::new("name", 3, |c: &Command| c.name.as_str().into()),
+///     RecordField::new("keys", 1, |c: &Command| c.keys.join(" ").into()),
+/// ];
+/// ```
+pub struct RecordField<T> {
+    /// Name reported in [`FieldMatch::name`].
+    pub name: &'static str,
+    /// Weight of the field relative to the other fields.
+    pub weight: usize,
+    /// Returns the text of the field.
+    pub key: Key<T>,
+}
+
+impl<T> RecordField<T> {
+    /// Creates a field named `name`, extracted by `key` and weighted by `weight`.
+    pub fn new<F>(name: &'static str, weight: usize, key: F) -> Self
+    where
+        F: Fn(&T) -> Cow<'_, str> + 'static,
+    {
+        RecordField {
+            name,
+            weight,
+            key: Box::new(key),
+        }
+    }
+}
+
+impl<T> fmt::Debug for RecordField<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecordField")
+            .field("name", &self.name)
+            .field("weight", &self.weight)
+            .finish_non_exhaustive()
+    }
+}
+
+/// How the scores of the fields of a record are combined into one.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum Combine {
+    /// The best field score, scaled by the field's weight over the largest weight.
+    #[default]
+    Max,
+    /// The sum of the field scores times their weights, divided by the total weight
+    /// so it stays within `0..=max_score`. Fields that do not match count as 0.
+    WeightedSum,
+    /// A custom function of the per-field results, capped at `max_score`.
+    Custom(fn(&[FieldMatch]) -> usize),
+}
+
+/// The result of matching the query against one field of a record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMatch {
+    /// Name of the field.
+    pub name: &'static str,
+    /// Weight of the field.
+    pub weight: usize,
+    /// The match in the field's text, or `None` if the query does not match it.
+    pub found: Option<Match>,
+}
+
+impl FieldMatch {
+    /// Returns the score of the field, 0 if it did not match.
+    pub fn score(&self) -> usize {
+        self.found.as_ref().map_or(0, |found| found.score)
+    }
+}
+
+/// A record that matched the query, as returned by [`RecordMatcher::find`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordMatch {
+    /// The combined score, between 0 and `config.max_score`.
+    pub score: usize,
+    /// Index in `fields` of the field with the best weighted score.
+    pub best: usize,
+    /// The result of every field, in the order the fields were given.
+    pub fields: Vec<FieldMatch>,
+}
+
+impl RecordMatch {
+    /// Returns the field with the best weighted score, e.g. to highlight its positions.
+    pub fn best_field(&self) -> &FieldMatch {
+        &self.fields[self.best]
+    }
+}
+
+/// A query scored against several weighted fields of a record type `T`.
+///
+/// Every field is scored like [`score`](crate::score); the scores are then
+/// combined according to a [`Combine`] mode. A record matches if any of its
+/// fields does.
+///
+/// # Examples
+///
+/// ```
+/// use matchr::{RecordField, RecordMatcher};
+///
+/// struct Command {
+///     name: &'static str,
+///     category: &'static str,
+/// }
+///
+/// let fields = [
+///     RecordField::new("name", 4, |c: &Command| c.name.into()),
+///     RecordField::new("category", 1, |c: &Command| c.category.into()),
+/// ];
+/// let mut matcher = RecordMatcher::new("File", &fields);
+///
+/// let open = Command { name: "Open File", category: "Editor" };
+/// let found = matcher.find(&open).unwrap();
+/// assert_eq!(found.best_field().name, "name");
+///
+/// let close = Command { name: "Close Tab", category: "File" };
+/// assert!(matcher.score(&close) < found.score);
+/// ```
+pub struct RecordMatcher<'f, T> {
+    matcher: Matcher,
+    fields: &'f [RecordField<T>],
+    combine: Combine,
+}
+
+impl<T> Clone for RecordMatcher<'_, T> {
+    fn clone(&self) -> Self {
+        RecordMatcher {
+            matcher: self.matcher.clone(),
+            fields: self.fields,
+            combine: self.combine,
+        }
+    }
+}
+
+impl<T> fmt::Debug for RecordMatcher<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecordMatcher")
+            .field("matcher", &self.matcher)
+            .field("fields", &self.fields)
+            .field("combine", &self.combine)
+            .finish()
+    }
+}
+
+impl<'f, T> RecordMatcher<'f, T> {
+    /// Compiles `query` for `fields` with the default [`ScoringConfig`], combining
+    /// the field scores with [`Combine::Max`].
+    pub fn new(query: &str, fields: &'f [RecordField<T>]) -> Self {
+        Self::with_config(query, fields, Combine::default(), ScoringConfig::default())
+    }
+
+    /// Compiles `query` for `fields`, combining the field scores with `combine`
+    /// and scoring every field with `config`.
+    pub fn with_config(
+        query: &str,
+        fields: &'f [RecordField<T>],
+        combine: Combine,
+        config: ScoringConfig,
+    ) -> Self {
+        RecordMatcher {
+            matcher: Matcher::with_config(query, config),
+            fields,
+            combine,
+        }
+    }
+
+    /// Scores `record`, 0 if none of its fields match.
+    pub fn score(&mut self, record: &T) -> usize {
+        self.find(record).map_or(0, |found| found.score)
+    }
+
+    /// Matches the query against every field of `record`.
+    ///
+    /// # Returns
+    ///
+    /// `Some(RecordMatch)` with the combined score, the best field and every
+    /// field's result, or `None` if no field matches.
+    pub fn find(&mut self, record: &T) -> Option<RecordMatch> {
+        self.find_with_tie(record).map(|(found, _)| found)
+    }
+
+    /// Like [`RecordMatcher::find`], also returning the tie-break keys of the best field.
+    fn find_with_tie(&mut self, record: &T) -> Option<(RecordMatch, Tie)> {
+        let max_weight = self
+            .fields
+            .iter()
+            .map(|f| f.weight)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let mut best: Option<(usize, usize, Tie)> = None;
+        let mut fields = Vec::with_capacity(self.fields.len());
+        for (index, field) in self.fields.iter().enumerate() {
+            let text = (field.key)(record);
+            let found = self.matcher.find(&text);
+            if let Some(found) = &found {
+                let weighted = found.score * field.weight;
+                if best.as_ref().is_none_or(|&(_, top, _)| weighted > top) {
+                    best = Some((index, weighted, Tie::new(&self.matcher, &text, true)));
+                }
+            }
+            fields.push(FieldMatch {
+                name: field.name,
+                weight: field.weight,
+                found,
+            });
+        }
+        let (best, top, tie) = best?;
+
+        let max_score = self.matcher.config().max_score;
+        let score = match self.combine {
+            Combine::Max => top / max_weight,
+            Combine::WeightedSum => {
+                let total: usize = fields.iter().map(|f| f.weight).sum();
+                let sum: usize = fields.iter().map(|f| f.score() * f.weight).sum();
+                sum / total.max(1)
+            }
+            Combine::Custom(combine) => combine(&fields).min(max_score),
+        };
+        Some((
+            RecordMatch {
+                score,
+                best,
+                fields,
+            },
+            tie,
+        ))
+    }
+}
+
+/// Matches `records` against the `query` on the given weighted `fields` and
+/// returns the matching records that reach `options.min_score`, best first, at
+/// most `options.limit` of them.
+///
+/// # Arguments
+///
+/// * `query` - The search query string slice.
+/// * `records` - Slice of records to be matched.
+/// * `fields` - The fields of a record to match, with their weights.
+/// * `combine` - How the field scores are combined.
+/// * `options` - The scoring options, threshold and limit.
+///
+/// # Returns
+///
+/// A vector of tuples `(record, match)` borrowing from `records`, sorted by
+/// descending score. Records with equal scores are ranked like
+/// [`filter_items`](crate::filter_items), on their best field.
+///
+/// # Examples
+///
+/// ```
+/// use matchr::{filter_records, Combine, MatchOptions, RecordField};
+///
+/// let palette = [
+///     ("Toggle Sidebar", "View", "Show or hide the file tree"),
+///     ("Find in files", "Search", "Search text in the workspace"),
+/// ];
+/// let fields = [
+///     RecordField::new("name", 5, |c: &(&str, &str, &str)| c.0.into()),
+///     RecordField::new("category", 2, |c: &(&str, &str, &str)| c.1.into()),
+///     RecordField::new("description", 1, |c: &(&str, &str, &str)| c.2.into()),
+/// ];
+/// let results = filter_records("file", &palette, &fields, Combine::Max, &MatchOptions::default());
+/// assert_eq!(results[0].0 .0, "Find in files");
+/// assert_eq!(results[1].1.best_field().name, "description");
+/// ```
+pub fn filter_records<'a, T>(
+    query: &str,
+    records: &'a [T],
+    fields: &[RecordField<T>],
+    combine: Combine,
+    options: &MatchOptions,
+) -> Vec<(&'a T, RecordMatch)> {
+    let mut matcher = RecordMatcher::with_config(query, fields, combine, options.config);
+    let scored = records.iter().filter_map(|record| {
+        let (found, tie) = matcher.find_with_tie(record)?;
+        let score = found.score;
+        Some(((record, found), score, tie))
+    });
+    select(scored, options)
+        .into_iter()
+        .map(|(entry, _)| entry)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Command {
+        name: &'static str,
+        category: &'static str,
+        description: &'static str,
+    }
+
+    const PALETTE: [Command; 3] = [
+        Command {
+            name: "Git: Commit",
+            category: "Source Control",
+            description: "Record staged changes",
+        },
+        Command {
+            name: "Go to Line",
+            category: "Navigation",
+            description: "Jump to a line of the current file",
+        },
+        Command {
+            name: "Format Document",
+            category: "Editor",
+            description: "Reformat the current file",
+        },
+    ];
+
+    fn fields() -> [RecordField<Command>; 3] {
+        [
+            RecordField::new("name", 4, |c: &Command| c.name.into()),
+            RecordField::new("category", 2, |c: &Command| c.category.into()),
+            RecordField::new("description", 1, |c: &Command| c.description.into()),
+        ]
+    }
+
+    #[test]
+    fn test_weights_favor_name_hits() {
+        let fields = fields();
+        let mut matcher = RecordMatcher::new("Format", &fields);
+        let found = matcher.find(&PALETTE[2]).unwrap();
+        assert_eq!(found.best, 0);
+        assert_eq!(found.score, crate::score("Format", "Format Document"));
+        assert_eq!(found.fields[2].name, "description");
+        assert!(found.fields[2].found.is_none());
+        assert_eq!(
+            found.best_field().found.as_ref().unwrap().positions,
+            [0, 1, 2, 3, 4, 5]
+        );
+        assert!(matcher.find(&PALETTE[0]).is_none());
+
+        // A weak hit in a heavy field beats a strong hit in a light one.
+        let found = RecordMatcher::new("on", &fields).find(&PALETTE[1]).unwrap();
+        assert_eq!(found.best_field().name, "name");
+        assert_eq!(found.score, found.best_field().score());
+        assert!(found.fields[1].score() > found.score);
+    }
+
+    #[test]
+    fn test_combine_modes() {
+        let record = &PALETTE[0];
+        let fields = fields();
+        let score = |combine| {
+            RecordMatcher::with_config("co", &fields, combine, ScoringConfig::default())
+                .score(record)
+        };
+        let category = crate::score("co", record.category);
+        let description = crate::score("co", record.description);
+        assert_eq!(crate::score("co", record.name), 0);
+        assert_eq!(score(Combine::Max), (category * 2).max(description) / 4);
+        assert_eq!(
+            score(Combine::WeightedSum),
+            (category * 2 + description) / 7
+        );
+        let sum = Combine::Custom(|fields| fields.iter().map(FieldMatch::score).sum());
+        assert_eq!(score(sum), (category + description).min(100));
+        assert_eq!(score(Combine::Custom(|_| 7)), 7);
+    }
+
+    #[test]
+    fn test_owned_and_capturing_keys() {
+        let prefix = String::from("cmd:");
+        let fields = [
+            RecordField::new("label", 2, move |c: &Command| {
+                format!("{prefix}{}", c.name).into()
+            }),
+            RecordField::new("words", 1, |c: &Command| {
+                c.description
+                    .split(' ')
+                    .rev()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .into()
+            }),
+        ];
+        let found = RecordMatcher::new("cmdGit", &fields)
+            .find(&PALETTE[0])
+            .unwrap();
+        assert_eq!(found.best_field().name, "label");
+        assert_eq!(found.score, crate::score("cmdGit", "cmd:Git: Commit"));
+
+        let found = RecordMatcher::new("changes", &fields)
+            .find(&PALETTE[0])
+            .unwrap();
+        assert_eq!(found.best_field().name, "words");
+        assert_eq!(found.best_field().found.as_ref().unwrap().positions[0], 0);
+    }
+
+    #[test]
+    fn test_matcher_clones_without_bounds() {
+        // `Command` is neither `Clone` nor `Debug`.
+        let fields = fields();
+        let matcher = RecordMatcher::new("Format", &fields);
+        let mut copy = matcher.clone();
+        assert_eq!(
+            copy.score(&PALETTE[2]),
+            crate::score("Format", "Format Document")
+        );
+        let debug = format!("{matcher:?}");
+        assert!(debug.starts_with("RecordMatcher {"));
+        assert!(debug.contains("name: \"category\""));
+    }
+
+    #[test]
+    fn test_filter_records_options() {
+        let options = MatchOptions {
+            limit: Some(1),
+            ..Default::default()
+        };
+        let results = filter_records("e", &PALETTE, &fields(), Combine::WeightedSum, &options);
+        assert_eq!(results.len(), 1);
+        let all = filter_records(
+            "e",
+            &PALETTE,
+            &fields(),
+            Combine::WeightedSum,
+            &Default::default(),
+        );
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].1, results[0].1);
+        assert!(all.windows(2).all(|w| w[0].1.score >= w[1].1.score));
+    }
+}
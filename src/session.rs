@@ -0,0 +1,197 @@
+use crate::filter::{select, Tie};
+use crate::{MatchOptions, Matcher};
+
+/// An interactive search over a fixed list of items.
+///
+/// The session remembers which items matched the previous query. When the next
+/// query extends it (the user typed another character), only those items can
+/// still match, so only they are re-scored. Any other edit, or an extension
+/// that changes how the query is split into `tokenize` terms or `path`
+/// segments, falls back to a full scan. Either way the result equals a fresh [`filter_items`](crate::filter_items) call.
+///
+/// # Examples
+///
+/// ```
+/// use matchr::Session;
+///
+/// let items = ["xbps-install", "xbps-remove", "grep", "xargs"];
+/// let mut session = Session::new(&items);
+/// assert_eq!(session.search("x").len(), 3);
+/// assert_eq!(session.search("xb").len(), 2);
+/// assert_eq!(session.search("xbr")[0].0, "xbps-remove");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Session<'a> {
+    items: &'a [&'a str],
+    options: MatchOptions,
+    query: String,
+    // Number of terms and path segments `query` was split into.
+    tokens: usize,
+    segments: usize,
+    // Indices of the items matching `query`, in input order.
+    matches: Vec<usize>,
+}
+
+impl<'a> Session<'a> {
+    /// Starts a session over `items` with the default [`MatchOptions`].
+    pub fn new(items: &'a [&'a str]) -> Self {
+        Self::with_options(items, MatchOptions::default())
+    }
+
+    /// Starts a session over `items` with the given `options`.
+    pub fn with_options(items: &'a [&'a str], options: MatchOptions) -> Self {
+        Session {
+            items,
+            options,
+            query: String::new(),
+            tokens: 0,
+            segments: 0,
+            matches: Vec::new(),
+        }
+    }
+
+    /// Returns the last query passed to [`Session::search`].
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Matches the items against `query`.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The search query string slice.
+    ///
+    /// # Returns
+    ///
+    /// A vector of tuples `(item, score)` of the matching items, sorted by
+    /// descending score, filtered and limited by the session's options.
+    pub fn search(&mut self, query: &str) -> Vec<(&'a str, usize)> {
+        let mut matcher = Matcher::with_config(query, self.options.config);
+        // Typos are limited by the query length, so a longer query may match
+        // items the shorter one did not. Likewise, a lone ` ` matches only items
+        // containing a space, but ` a` is the term `a`, and `/` matches only
+        // paths containing a separator, but `/a` is the segment query `a`, also
+        // as a term of a `tokenize` mode query.
+        let refine = self.options.config.max_typos == 0
+            && !self.query.is_empty()
+            && query.starts_with(self.query.as_str())
+            && matcher.token_count() == self.tokens
+            && matcher.segment_count() == self.segments;
+        if !refine {
+            self.matches.clear();
+            self.matches.extend(0..self.items.len());
+        }
+        let candidates = std::mem::take(&mut self.matches);
+
+        let mut matches = Vec::with_capacity(candidates.len());
+        let mut scored = Vec::new();
+        for index in candidates {
+            let item = self.items[index];
+            if let Some(score) = matcher.try_score(item) {
+                matches.push(index);
+                scored.push((item, score, Tie::new(&matcher, item, true)));
+            }
+        }
+
+        self.query = query.to_string();
+        self.tokens = matcher.token_count();
+        self.segments = matcher.segment_count();
+        self.matches = matches;
+        select(scored, &self.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{filter_items, match_items, CaseMode, ScoringConfig};
+
+    const ITEMS: [&str; 10] = [
+        "xbps-install",
+        "xbps-remove",
+        "xbps-query",
+        "Xorg",
+        "grep",
+        "xargs",
+        "bash",
+        "box",
+        "src/xbps/Builder.rs",
+        "abc",
+    ];
+
+    #[test]
+    fn test_session_equals_fresh_search() {
+        let keystrokes = [
+            "", "x", "xb", "xbp", "xbps", "xbpsq", "xbps", "xr", "b", "bB", "b", "xp", "xpb", "/",
+            "/c", "//c", "s/", "s/x", "s/xb", "s/xb/", "s/xb/B", " ", " a", "  c", " /", " /c",
+            "x", "x ", "x b", "x bs", "x bs ",
+        ];
+        let smart = ScoringConfig {
+            case: CaseMode::Smart,
+            ..Default::default()
+        };
+        let typos = ScoringConfig {
+            max_typos: 1,
+            ..smart
+        };
+        let path = ScoringConfig {
+            path: true,
+            ..smart
+        };
+        let tokenize = ScoringConfig {
+            tokenize: true,
+            ..smart
+        };
+        let path_tokenize = ScoringConfig {
+            tokenize: true,
+            ..path
+        };
+        let configs = [
+            (smart, None),
+            (smart, Some(2)),
+            (typos, None),
+            (path, None),
+            (tokenize, None),
+            (path_tokenize, None),
+        ];
+        for (config, limit) in configs {
+            let options = MatchOptions {
+                config,
+                limit,
+                ..Default::default()
+            };
+            let mut session = Session::with_options(&ITEMS, options);
+            for query in keystrokes {
+                assert_eq!(
+                    session.search(query),
+                    filter_items(query, &ITEMS, &options),
+                    "{query}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_session_agrees_with_match_items() {
+        let mut session = Session::new(&ITEMS);
+        for query in ["x", "xb", "xbs", "xbsi"] {
+            let expected: Vec<_> = match_items(query, &ITEMS)
+                .into_iter()
+                .filter(|(_, score)| *score > 0)
+                .collect();
+            assert_eq!(session.search(query), expected);
+        }
+        assert_eq!(session.query(), "xbsi");
+    }
+
+    #[test]
+    fn test_session_refines_cached_matches() {
+        let mut session = Session::new(&ITEMS);
+        session.search("xb");
+        assert_eq!(session.matches, vec![0, 1, 2, 8]);
+        session.search("xbq");
+        assert_eq!(session.matches, vec![2]);
+        session.search("x");
+        assert_eq!(session.matches, vec![0, 1, 2, 5, 7, 8]);
+    }
+}